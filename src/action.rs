@@ -6,6 +6,14 @@ use crate::{card::Card, suit::Suit};
 pub enum Action {
     /// None means play without trump
     PickTrump(Option<Suit>),
+    /// the dealer lets their partner pick trump instead
+    PassTrump,
+    /// the opponents of the trump picking team double the stakes
+    Double,
+    /// the trump picking team doubles the stakes once more
+    Redouble,
+    /// don't (re)double
+    Pass,
     PlayCard(Card),
 }
 
@@ -14,6 +22,10 @@ impl Display for Action {
         match *self {
             Action::PickTrump(Some(suit)) => write!(f, "{suit}"),
             Action::PickTrump(None) => write!(f, "None"),
+            Action::PassTrump => write!(f, "Pass trump"),
+            Action::Double => write!(f, "Double"),
+            Action::Redouble => write!(f, "Redouble"),
+            Action::Pass => write!(f, "Pass"),
             Action::PlayCard(card) => write!(f, "{card}"),
         }
     }
@@ -11,13 +11,24 @@ use crate::{
 
 const NO_TRUMP_INDEX: u8 = 4;
 const NO_TRUMP_MASK: u8 = 1 << NO_TRUMP_INDEX;
+const PASS_TRUMP_INDEX: u8 = 5;
+pub const PASS_TRUMP_MASK: u8 = 1 << PASS_TRUMP_INDEX;
+
+pub const PASS_MASK: u8 = 1;
+pub const DOUBLE_MASK: u8 = 1 << 1;
+pub const REDOUBLE_MASK: u8 = 1 << 2;
 
 #[derive(Clone, Copy)]
 pub enum ActionCollection {
     Cards(Stack),
     /// bit 0..=3 are your regular suits
     /// bit 4 means without trump
+    /// bit 5 means passing the choice to your partner
     Trumps(u8),
+    /// bit 0 means pass
+    /// bit 1 means double
+    /// bit 2 means redouble
+    Bets(u8),
     Uninit,
 }
 
@@ -30,13 +41,17 @@ impl ActionCollection {
                 let mut bits = bits as u32;
                 while bits != 0 {
                     let idx = pop_lsb(&mut bits);
-                    let action = if idx == NO_TRUMP_INDEX as _ {
-                        None
-                    } else {
-                        Some(Suit::from(idx as u8))
-                    };
+                    actions.push(trump_action(idx as u8));
+                }
 
-                    actions.push(Action::PickTrump(action));
+                actions
+            }
+            ActionCollection::Bets(bits) => {
+                let mut actions = vec![];
+                let mut bits = bits as u32;
+                while bits != 0 {
+                    let idx = pop_lsb(&mut bits);
+                    actions.push(bet_action(idx as u8));
                 }
 
                 actions
@@ -46,6 +61,41 @@ impl ActionCollection {
     }
 }
 
+fn trump_action(index: u8) -> Action {
+    match index {
+        NO_TRUMP_INDEX => Action::PickTrump(None),
+        PASS_TRUMP_INDEX => Action::PassTrump,
+        _ => Action::PickTrump(Some(Suit::from(index))),
+    }
+}
+
+const fn bet_action(index: u8) -> Action {
+    match 1 << index {
+        PASS_MASK => Action::Pass,
+        DOUBLE_MASK => Action::Double,
+        REDOUBLE_MASK => Action::Redouble,
+        _ => panic!(),
+    }
+}
+
+const fn bet_bit(action: Action) -> u8 {
+    match action {
+        Action::Pass => PASS_MASK,
+        Action::Double => DOUBLE_MASK,
+        Action::Redouble => REDOUBLE_MASK,
+        _ => panic!(),
+    }
+}
+
+const fn trump_bit(action: Action) -> u8 {
+    match action {
+        Action::PickTrump(Some(suit)) => 1 << suit as u8,
+        Action::PickTrump(None) => NO_TRUMP_MASK,
+        Action::PassTrump => PASS_TRUMP_MASK,
+        _ => panic!(),
+    }
+}
+
 impl ActionList<Action> for ActionCollection {
     fn uninit() -> Self {
         ActionCollection::Uninit
@@ -56,25 +106,21 @@ impl ActionList<Action> for ActionCollection {
             (ActionCollection::Cards(ref mut stack), Action::PlayCard(card)) => {
                 *stack |= 1 << card.get_index()
             }
-            (ActionCollection::Trumps(bits), Action::PickTrump(trump)) => {
-                if let Some(suit) = trump {
-                    *bits |= 1 << suit as u8;
-                } else {
-                    *bits |= 1 << NO_TRUMP_INDEX;
-                }
+            (ActionCollection::Trumps(bits), Action::PickTrump(_) | Action::PassTrump) => {
+                *bits |= trump_bit(action);
+            }
+            (ActionCollection::Bets(bits), Action::Pass | Action::Double | Action::Redouble) => {
+                *bits |= bet_bit(action);
             }
             (this @ ActionCollection::Uninit, Action::PlayCard(card)) => {
                 let stack = Stack::default() | 1 << card.get_index();
                 *this = ActionCollection::Cards(stack);
             }
-            (this @ ActionCollection::Uninit, Action::PickTrump(trump)) => {
-                let bits = if let Some(suit) = trump {
-                    1 << suit as u8
-                } else {
-                    1 << NO_TRUMP_INDEX
-                };
-
-                *this = ActionCollection::Trumps(bits);
+            (this @ ActionCollection::Uninit, Action::PickTrump(_) | Action::PassTrump) => {
+                *this = ActionCollection::Trumps(trump_bit(action));
+            }
+            (this @ ActionCollection::Uninit, Action::Pass | Action::Double | Action::Redouble) => {
+                *this = ActionCollection::Bets(bet_bit(action));
             }
             _ => unreachable!(),
         }
@@ -89,15 +135,19 @@ impl ActionList<Action> for ActionCollection {
                     None
                 } else {
                     let index = select_random_set_bit(*bits as _);
-                    let choice = if index == NO_TRUMP_INDEX as _ {
-                        None
-                    } else {
-                        Some(Suit::from(index as u8))
-                    };
+                    *bits ^= 1 << index;
 
+                    Some(trump_action(index as u8))
+                }
+            }
+            ActionCollection::Bets(bits) => {
+                if *bits == 0 {
+                    None
+                } else {
+                    let index = select_random_set_bit(*bits as _);
                     *bits ^= 1 << index;
 
-                    Some(Action::PickTrump(choice))
+                    Some(bet_action(index as u8))
                 }
             }
         }
@@ -107,6 +157,7 @@ impl ActionList<Action> for ActionCollection {
         match self {
             ActionCollection::Cards(stack) => stack.len() as _,
             ActionCollection::Trumps(bits) => bits.count_ones() as _,
+            ActionCollection::Bets(bits) => bits.count_ones() as _,
             ActionCollection::Uninit => 0,
         }
     }
@@ -114,12 +165,11 @@ impl ActionList<Action> for ActionCollection {
     fn has(&self, item: &Action) -> bool {
         match (self, item) {
             (ActionCollection::Cards(stack), Action::PlayCard(card)) => stack.has_card(*card),
-            (ActionCollection::Trumps(bits), Action::PickTrump(trump)) => {
-                if let Some(suit) = trump {
-                    *bits & 1 << *suit as u8 != 0
-                } else {
-                    *bits & NO_TRUMP_MASK != 0
-                }
+            (ActionCollection::Trumps(bits), Action::PickTrump(_) | Action::PassTrump) => {
+                *bits & trump_bit(*item) != 0
+            }
+            (ActionCollection::Bets(bits), Action::Pass | Action::Double | Action::Redouble) => {
+                *bits & bet_bit(*item) != 0
             }
             (ActionCollection::Uninit, _) => false,
             _ => unreachable!(),
@@ -134,6 +184,9 @@ impl ActionList<Action> for ActionCollection {
             (ActionCollection::Trumps(bits), ActionCollection::Trumps(other_bits)) => {
                 ActionCollection::Trumps(*bits & !*other_bits)
             }
+            (ActionCollection::Bets(bits), ActionCollection::Bets(other_bits)) => {
+                ActionCollection::Bets(*bits & !*other_bits)
+            }
             (this, ActionCollection::Uninit) => *this,
             _ => unreachable!(),
         }
@@ -154,8 +207,13 @@ impl Debug for ActionCollection {
                     suits.push(None);
                 }
 
-                writeln!(f, "{suits:?}")
+                if bits & PASS_TRUMP_MASK != 0 {
+                    writeln!(f, "{suits:?}, pass")
+                } else {
+                    writeln!(f, "{suits:?}")
+                }
             }
+            Self::Bets(_) => writeln!(f, "{:?}", self.to_vec()),
             Self::Uninit => writeln!(f, "Uninit"),
         }
     }
@@ -1,27 +1,20 @@
 use std::time::Instant;
 
-use crate::{
-    game::Game,
-    players::{random_player::RandomPlayer, Player, PlayerVec},
-};
+use crate::{game::Game, players::PlayerVec, rules::Rules};
 
 const BENCH_SIZE: usize = 800_000;
 
-pub fn bench(size: Option<usize>) {
+pub fn bench(size: Option<usize>, rules: Rules, name: &str, player_gen: impl Fn() -> PlayerVec) {
     let size = size.unwrap_or(BENCH_SIZE);
 
-    start_simple_bench::<RandomPlayer>(size, "random");
-}
-
-fn start_simple_bench<T: Player + Default + 'static>(size: usize, name: &str) {
-    let player_gen = || -> PlayerVec { vec![T::boxed(), T::boxed(), T::boxed(), T::boxed()] };
-    run_bench(size, name, false, player_gen);
+    run_bench(size, name, false, rules, player_gen);
 }
 
 fn run_bench(
     size: usize,
     name: &str,
     verbose: bool,
+    rules: Rules,
     player_gen: impl Fn() -> PlayerVec,
 ) -> [i32; 2] {
     println!("Simulating {size} random games for {name}...");
@@ -29,16 +22,14 @@ fn run_bench(
     let mut games = Vec::with_capacity(size);
 
     for _ in 0..size {
-        games.push(Game::new(player_gen()));
+        games.push((Game::new(rules), player_gen()));
     }
 
     let start = Instant::now();
     let mut total_rounds = 0;
 
-    for game in &mut games {
-        while !game.is_terminal() {
-            game.play_round();
-        }
+    for (game, players) in &mut games {
+        game.play(players);
 
         total_rounds += game.num_rounds();
 
@@ -55,7 +46,7 @@ fn run_bench(
     println!("avg num of rounds: {}", total_rounds as f64 / size as f64);
 
     let mut score = [0; 2];
-    for game in &mut games {
+    for (game, _) in &mut games {
         score[game.winner()] += 1;
     }
 
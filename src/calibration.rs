@@ -0,0 +1,227 @@
+//! measures how well [`Inference`] predicts the hidden hands. Self-play rounds are
+//! played and after every action, every player predicts who holds each card it
+//! can't see. The full inference is compared with two baselines: one that only
+//! knows the number of cards in every hand, and one that also knows the certain
+//! exclusions, so the soft weights are judged on their own
+use ismcts::state::State;
+
+use crate::{
+    card::Card, game::Game, inference::Inference, players::PlayerVec, round::Round, rules::Rules,
+};
+
+const NUM_BUCKETS: usize = 10;
+/// predictions are clamped to this, so a confident mistake doesn't score infinity
+const MIN_PROBABILITY: f64 = 1e-6;
+
+#[derive(Clone, Copy)]
+enum Predictor {
+    /// only the number of cards in every hand
+    Counts,
+    /// the counts and the cards each player certainly doesn't hold
+    Constraints,
+    Inference,
+}
+
+const PREDICTORS: [(Predictor, &str); 3] = [
+    (Predictor::Counts, "card counts"),
+    (Predictor::Constraints, "constraints"),
+    (Predictor::Inference, "inference"),
+];
+
+#[derive(Default, Clone, Copy)]
+struct Bucket {
+    sum_predicted: f64,
+    num_hits: usize,
+    num_predictions: usize,
+}
+
+/// the scores of one predictor over all predicted cards
+#[derive(Default)]
+struct Scores {
+    num_cards: usize,
+    log_loss: f64,
+    brier: f64,
+    buckets: [Bucket; NUM_BUCKETS],
+}
+
+impl Scores {
+    /// `probabilities` of each candidate holding the card, `holder` is the actual one
+    fn add(&mut self, probabilities: [f64; 3], holder: usize) {
+        self.num_cards += 1;
+        self.log_loss -= probabilities[holder].max(MIN_PROBABILITY).ln();
+
+        for (i, &probability) in probabilities.iter().enumerate() {
+            let hit = i == holder;
+            self.brier += (probability - hit as u8 as f64).powi(2);
+
+            let index = ((probability * NUM_BUCKETS as f64) as usize).min(NUM_BUCKETS - 1);
+            let bucket = &mut self.buckets[index];
+            bucket.sum_predicted += probability;
+            bucket.num_hits += hit as usize;
+            bucket.num_predictions += 1;
+        }
+    }
+
+    fn mean_log_loss(&self) -> f64 {
+        self.log_loss / self.num_cards as f64
+    }
+
+    fn mean_brier(&self) -> f64 {
+        self.brier / self.num_cards as f64
+    }
+}
+
+/// the probability that each of the other players holds `card`, as seen by `observer`
+fn predict(
+    round: &Round,
+    inference: &Inference,
+    weights: &[[f32; 32]; 4],
+    observer: usize,
+    card: Card,
+    predictor: Predictor,
+) -> [f64; 3] {
+    let others = [1, 2, 3].map(|i| (observer + i) % 4);
+    let count = |player: usize| round.player_cards(player).len() as f64;
+    let counts = others.map(count);
+
+    let weights = others.map(|player| {
+        let can_have = inference.can_have(player, card) as u8 as f64;
+        match predictor {
+            Predictor::Counts => count(player),
+            Predictor::Constraints => count(player) * can_have,
+            // the weights of a player sum to 1 over the cards it might hold,
+            // times its number of cards that's about the chance it holds this one
+            Predictor::Inference => {
+                count(player) * can_have * weights[player][card.get_index() as usize] as f64
+            }
+        }
+    });
+
+    let total = weights.iter().sum::<f64>();
+    if total > 0. {
+        weights.map(|weight| weight / total)
+    } else {
+        let total = counts.iter().sum::<f64>();
+        counts.map(|count| count / total)
+    }
+}
+
+/// scores the predictions of every observer for every card it can't see
+fn record(round: &Round, inference: &Inference, scores: &mut [Scores; 3]) {
+    let weights = std::array::from_fn(|player| inference.weights(player));
+
+    for observer in 0..4 {
+        let hidden = round.unplayed_cards() & !round.player_cards(observer);
+
+        for card in hidden.into_iter() {
+            let holder = (1..=3)
+                .position(|i| round.player_cards((observer + i) % 4).has_card(card))
+                .unwrap();
+
+            for (&(predictor, _), scores) in PREDICTORS.iter().zip(scores.iter_mut()) {
+                scores.add(
+                    predict(round, inference, &weights, observer, card, predictor),
+                    holder,
+                );
+            }
+        }
+    }
+}
+
+fn play_rounds(num_rounds: usize, rules: Rules, player_gen: impl Fn() -> PlayerVec) -> [Scores; 3] {
+    let mut scores: [Scores; 3] = Default::default();
+
+    for _ in 0..num_rounds {
+        let mut game = Game::new(rules);
+        let mut players = player_gen();
+
+        while !game.round().is_terminal() {
+            let action = players[game.current_player()].decide(*game.round(), game.inference());
+            for player in players.iter_mut() {
+                player.observe(game.round(), action);
+            }
+            game.apply_action(action);
+
+            if !game.round().is_terminal() {
+                record(game.round(), game.inference(), &mut scores);
+            }
+        }
+    }
+
+    scores
+}
+
+/// plays `num_rounds` rounds and prints how well the hidden cards were predicted
+pub fn run_calibration(num_rounds: usize, rules: Rules, player_gen: impl Fn() -> PlayerVec) {
+    println!("predicting hidden cards over {num_rounds} rounds");
+    let scores = play_rounds(num_rounds, rules, player_gen);
+
+    println!("predictor\tcards\tlog-loss\tbrier");
+    for ((_, name), scores) in PREDICTORS.iter().zip(&scores) {
+        println!(
+            "{name}\t{}\t{:.4}\t\t{:.4}",
+            scores.num_cards,
+            scores.mean_log_loss(),
+            scores.mean_brier()
+        );
+    }
+
+    // a calibrated predictor is right as often as it predicts
+    println!("\ncalibration, predicted vs observed frequency");
+    print!("bucket");
+    for (_, name) in PREDICTORS {
+        print!("\t{name}");
+    }
+    println!();
+    for i in 0..NUM_BUCKETS {
+        print!("{:.1}-{:.1}", i as f64 / 10., (i + 1) as f64 / 10.);
+        for scores in &scores {
+            let bucket = scores.buckets[i];
+            match bucket.num_predictions {
+                0 => print!("\t-"),
+                n => print!(
+                    "\t{:.3}/{:.3}",
+                    bucket.sum_predicted / n as f64,
+                    bucket.num_hits as f64 / n as f64
+                ),
+            }
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play_rounds, Scores};
+    use crate::{
+        players::{heuristic_player::HeuristicPlayer, Player, PlayerVec},
+        random,
+        rules::Rules,
+    };
+
+    #[test]
+    fn test_scores() {
+        let mut scores = Scores::default();
+        scores.add([0., 1., 0.], 1);
+        assert!(scores.mean_log_loss().abs() < 1e-9);
+        assert!(scores.mean_brier().abs() < 1e-9);
+
+        scores.add([0.5, 0.25, 0.25], 2);
+        assert!((scores.mean_log_loss() - 4f64.ln() / 2.).abs() < 1e-9);
+        assert!((scores.mean_brier() - 0.875 / 2.).abs() < 1e-9);
+        assert_eq!(scores.buckets[2].num_predictions, 2);
+        assert_eq!(scores.buckets[2].num_hits, 1);
+    }
+
+    #[test]
+    fn test_constraints_help() {
+        random::seed(Some(17));
+        let player_gen =
+            || -> PlayerVec { (0..4).map(|_| HeuristicPlayer::boxed() as _).collect() };
+        let [counts, constraints, _] = play_rounds(10, Rules::default(), player_gen);
+
+        // certain exclusions only move probability to the actual holders
+        assert_eq!(counts.num_cards, constraints.num_cards);
+        assert!(constraints.mean_log_loss() < counts.mean_log_loss());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::suit::Suit;
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 /// Some info on the following structures:
 /// card values range from 0..=7, with 0 being a seven, 1 an eight, ..., 7 a ten
@@ -97,9 +97,38 @@ impl fmt::Debug for Card {
     }
 }
 
+impl FromStr for Card {
+    type Err = String;
+
+    /// parses the format used by [`Display`](fmt::Display), e.g. `♥10` or `HQ`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let suit = chars
+            .next()
+            .ok_or_else(|| "empty card".to_owned())?
+            .to_string()
+            .parse::<Suit>()?;
+
+        let value = match chars.as_str() {
+            "7" => 0,
+            "8" => 1,
+            "9" => 2,
+            "J" => 3,
+            "Q" => 4,
+            "K" => 5,
+            "A" => 6,
+            "10" => 7,
+            _ => return Err(format!("invalid card '{s}'")),
+        };
+
+        Ok(Card::new(suit as u32 * 8 + value))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Card;
+    use crate::stack::Stack;
 
     #[test]
     fn test_to_index() {
@@ -107,4 +136,14 @@ mod tests {
         assert!(Card::new(4).get_index() == 4);
         assert!(Card::new(17).get_index() == 17);
     }
+
+    #[test]
+    fn test_parse() {
+        for card in Stack::ALL.into_iter() {
+            assert!(card.to_string().parse::<Card>() == Ok(card));
+        }
+
+        assert!("HQ".parse::<Card>() == Ok(Card::new(20)));
+        assert!("♥11".parse::<Card>().is_err());
+    }
 }
@@ -1,74 +1,201 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
 use ismcts::{action_list::ActionList, state::State};
 
-use crate::{action::Action, inference::Inference, players::PlayerVec, round::Round, stack::Stack};
+use crate::{
+    action::Action,
+    inference::Inference,
+    players::PlayerVec,
+    random::DealRng,
+    record::GameRecord,
+    round::Round,
+    rules::Rules,
+    scoreboard::{RoundSummary, Scoreboard},
+    stack::Stack,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    ActionPlayed { player: usize, action: Action },
+    TrickWon { player: usize, score: i16 },
+    RoundFinished(RoundSummary),
+    GameFinished { winner: usize, scores: [i16; 2] },
+}
 
-const MAX_SCORE: i16 = 61;
+impl Display for GameEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEvent::ActionPlayed { player, action } => {
+                write!(f, "player {player} played {action}")
+            }
+            GameEvent::TrickWon { player, score } => {
+                write!(f, "player {player} won the trick, worth {score} points")
+            }
+            GameEvent::RoundFinished(summary) => write!(
+                f,
+                "team {} won the round for {} points",
+                summary.winning_team, summary.points
+            ),
+            GameEvent::GameFinished { winner, scores } => {
+                write!(f, "team {winner} won the game: {scores:?}")
+            }
+        }
+    }
+}
 
+/// a game of multiple rounds, until one team reaches the target score.
+/// This only enforces the rules, players are driven from outside
+/// through [`Game::current_player`] and [`Game::apply_action`]
 #[derive(Default)]
 pub struct Game {
-    players: PlayerVec,
     round: Round,
     inference: Inference,
-    num_rounds: usize,
-    scores: [i16; 2],
+    scoreboard: Scoreboard,
+    /// events caused by the last action
+    events: Vec<GameEvent>,
+    record: Option<GameRecord>,
+    deal_rng: DealRng,
+}
+
+/// deal the first round of a game, with a random dealer
+fn first_round(rules: Rules, deal_rng: &DealRng) -> Round {
+    let dealer = deal_rng.mod_usize(4);
+    let mut round = Round::with_deal_rng(dealer, deal_rng);
+    round.set_rules(rules);
+
+    round
 }
 
 impl Game {
-    pub fn new(players: PlayerVec) -> Self {
-        let dealer = romu::range_usize(0..4);
+    pub fn new(rules: Rules) -> Self {
+        Self::with_deal_rng(rules, DealRng::default())
+    }
 
+    /// a game whose deals only depend on `seed`, two games with the same seed
+    /// get the same deals as long as they last
+    pub fn with_deal_seed(rules: Rules, seed: u64) -> Self {
+        Self::with_deal_rng(rules, DealRng::seeded(seed))
+    }
+
+    fn with_deal_rng(rules: Rules, deal_rng: DealRng) -> Self {
         Game {
-            players,
-            round: Round::new(dealer),
+            round: first_round(rules, &deal_rng),
             inference: Default::default(),
-            num_rounds: 0,
-            scores: [0; 2],
+            scoreboard: Default::default(),
+            events: Vec::with_capacity(4),
+            record: None,
+            deal_rng,
         }
     }
 
-    fn apply_action(&mut self, action: Action) {
-        self.inference.infer(&self.round, action, self.round.turn());
-        self.round.apply_action(action);
+    /// keep a record of all actions from now on, should be called
+    /// at the start of a round
+    pub fn enable_recording(&mut self) {
+        let mut record = GameRecord::new(*self.round.rules());
+        record.start_round(self.round.dealer(), self.hands());
+        self.record = Some(record);
     }
 
-    fn play_trick(&mut self) {
-        let turn = self.round.turn();
+    pub const fn record(&self) -> Option<&GameRecord> {
+        self.record.as_ref()
+    }
 
-        for i in turn..(turn + 4) {
-            let player_idx = i % 4;
-            let action = self.players[player_idx].decide(self.round, &self.inference);
+    fn hands(&self) -> [Stack; 4] {
+        std::array::from_fn(|i| self.round.player_cards(i))
+    }
 
-            match action {
-                Action::PlayCard(_) => {
-                    debug_assert!(self.is_legal(action));
+    /// returns the events caused by this action
+    pub fn apply_action(&mut self, action: Action) -> &[GameEvent] {
+        debug_assert!(self.is_legal(action));
 
-                    self.apply_action(action);
+        self.events.clear();
+        let player = self.round.turn();
+        let scores = self.round.scores();
+
+        self.inference.infer(&self.round, action, player);
+        self.round.apply_action(action);
+        self.events.push(GameEvent::ActionPlayed { player, action });
+
+        if let Some(record) = &mut self.record {
+            record.current_round().actions.push(action);
+        }
+
+        if let Action::PlayCard(_) = action {
+            if self.round.trick_ref().cards().len() == 0 {
+                let winner = self.round.turn();
+                let team = winner % 2;
+                self.events.push(GameEvent::TrickWon {
+                    player: winner,
+                    score: self.round.scores()[team] - scores[team],
+                });
+
+                if let Some(record) = &mut self.record {
+                    record.current_round().trick_winners.push(winner);
                 }
-                _ => unreachable!(),
             }
         }
+
+        if self.round.is_terminal() {
+            assert!(self.round.scores().iter().sum::<i16>() == 60);
+            self.scoreboard.record(&self.round);
+
+            if let Some(record) = &mut self.record {
+                record.current_round().trump = Some(self.round.trump());
+                record.current_round().card_scores = self.round.scores();
+                record.scores = self.scoreboard.scores();
+            }
+
+            let summary = *self.scoreboard.history().last().unwrap();
+            self.events.push(GameEvent::RoundFinished(summary));
+
+            if self.is_terminal() {
+                self.events.push(GameEvent::GameFinished {
+                    winner: self.winner(),
+                    scores: self.scoreboard.scores(),
+                });
+            }
+        }
+
+        &self.events
     }
 
-    /// play an entire round, i.e. 8 tricks
-    pub fn play_round(&mut self) {
+    /// deal the next round, should only be called once the current round is finished
+    pub fn next_round(&mut self) {
+        assert!(self.round.is_terminal() && !self.is_terminal());
+
+        self.round.setup_for_next_round(&self.deal_rng);
         self.inference = Inference::default();
-        self.round.setup_for_next_round();
 
-        let action = self.players[self.round.turn()].decide(self.round, &self.inference);
-        self.apply_action(action);
+        let hands = self.hands();
+        if let Some(record) = &mut self.record {
+            record.start_round(self.round.dealer(), hands);
+        }
+    }
 
-        for _ in 0..8 {
-            self.play_trick();
+    /// let `players` play an entire round, i.e. 8 tricks
+    pub fn play_round(&mut self, players: &mut PlayerVec) {
+        if self.round.is_terminal() {
+            self.next_round();
         }
 
-        let scores = self.round.scores();
-        let winning_team = if scores[0] > scores[1] { 0 } else { 1 };
-        self.scores[winning_team] += scores[winning_team] - 30;
+        while !self.round.is_terminal() {
+            let action = players[self.current_player()].decide(self.round, &self.inference);
+            for player in players.iter_mut() {
+                player.observe(&self.round, action);
+            }
+            self.apply_action(action);
+        }
+    }
 
-        assert!(scores.iter().sum::<i16>() == 60);
-        self.num_rounds += 1;
+    /// let `players` play until the game is finished
+    pub fn play(&mut self, players: &mut PlayerVec) {
+        while !self.is_terminal() {
+            self.play_round(players);
+        }
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.round.turn()
     }
 
     /// controleer of deze speler al dan niet kan volgen
@@ -80,63 +207,71 @@ impl Game {
         self.round.possible_actions()
     }
 
-    pub const fn player_cards(&self, player: usize) -> Stack {
-        self.round.player_cards(player)
+    pub const fn round(&self) -> &Round {
+        &self.round
+    }
+
+    pub const fn inference(&self) -> &Inference {
+        &self.inference
+    }
+
+    pub const fn scoreboard(&self) -> &Scoreboard {
+        &self.scoreboard
     }
 
-    pub const fn num_rounds(&self) -> usize {
-        self.num_rounds
+    pub fn num_rounds(&self) -> usize {
+        self.scoreboard.num_rounds()
     }
 
     pub fn is_terminal(&self) -> bool {
-        self.scores.iter().any(|&s| s >= MAX_SCORE)
+        self.scoreboard.is_finished(self.round.rules().target_score)
     }
 
     pub fn winner(&self) -> usize {
         assert!(self.is_terminal());
 
-        self.scores
-            .iter()
-            .enumerate()
-            .max_by_key(|(_, s)| **s)
-            .unwrap()
-            .0
+        self.scoreboard.winner()
     }
 }
 
 impl Debug for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "total score: {:?}", self.scores)?;
-        writeln!(f, "rounds: {}", self.num_rounds)?;
+        writeln!(f, "total score: {:?}", self.scoreboard.scores())?;
+        writeln!(f, "rounds: {}", self.num_rounds())?;
         writeln!(f, "{:?}", self.round)
     }
 }
 
 #[cfg(test)]
-mod tests {
-    use super::Game;
+pub(crate) mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::{Game, GameEvent};
     use crate::{
         players::{random_player::RandomPlayer, Player, PlayerVec},
+        random,
+        rules::Rules,
+        scoring::Scoring,
         stack::Stack,
     };
 
+    /// four players that play random legal actions
+    pub(crate) fn random_players() -> PlayerVec {
+        (0..4).map(|_| RandomPlayer::boxed() as _).collect()
+    }
+
     #[test]
     fn test_dealing() {
-        let players: PlayerVec = vec![
-            RandomPlayer::boxed(),
-            RandomPlayer::boxed(),
-            RandomPlayer::boxed(),
-            RandomPlayer::boxed(),
-        ];
+        let players = random_players();
 
-        let game = Game::new(players);
+        let game = Game::new(Rules::default());
         let mut seen_cards = Stack::default();
 
         for i in 0..4 {
-            let cards = game.player_cards(i);
+            let cards = game.round().player_cards(i);
             seen_cards |= cards;
 
-            assert!(cards.len() == Stack::ALL.len() / (game.players.len() as u32));
+            assert!(cards.len() == Stack::ALL.len() / (players.len() as u32));
         }
 
         assert!(seen_cards == Stack::ALL);
@@ -144,16 +279,110 @@ mod tests {
 
     #[test]
     fn test_random_game() {
-        let players: PlayerVec = vec![
-            RandomPlayer::boxed(),
-            RandomPlayer::boxed(),
-            RandomPlayer::boxed(),
-            RandomPlayer::boxed(),
+        let mut players = random_players();
+
+        let mut game = Game::new(Rules::default());
+        game.play(&mut players);
+    }
+
+    #[test]
+    fn test_seeded_game() {
+        let play = || {
+            random::seed(Some(42));
+            let mut players = random_players();
+
+            let mut game = Game::new(Rules::default());
+            game.enable_recording();
+            game.play(&mut players);
+            game.record().unwrap().to_string()
+        };
+
+        assert!(play() == play());
+    }
+
+    #[test]
+    fn test_duplicate_deals() {
+        let mut players = random_players();
+
+        let mut games = [
+            Game::with_deal_seed(Rules::default(), 3),
+            Game::with_deal_seed(Rules::default(), 3),
         ];
+        for game in &mut games {
+            game.enable_recording();
+            game.play(&mut players);
+        }
+
+        let [a, b] = games.map(|game| game.record().unwrap().rounds.clone());
+        for (a, b) in a.iter().zip(&b) {
+            assert!(a.dealer == b.dealer && a.hands == b.hands);
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let mut game = Game::new(Rules::default());
+        let mut num_tricks = 0;
+        let mut num_rounds = 0;
+        let mut finished = false;
+
+        while !finished {
+            if game.round().is_terminal() {
+                game.next_round();
+            }
+
+            let action = game.legal_actions().pop_random().unwrap();
+            for event in game.apply_action(action) {
+                match *event {
+                    GameEvent::TrickWon { .. } => num_tricks += 1,
+                    GameEvent::RoundFinished(_) => num_rounds += 1,
+                    GameEvent::GameFinished { .. } => finished = true,
+                    GameEvent::ActionPlayed { .. } => {}
+                }
+            }
+        }
+
+        assert!(game.is_terminal());
+        assert!(num_rounds == game.num_rounds());
+        assert!(num_tricks == 8 * num_rounds);
+    }
+
+    #[test]
+    fn test_scored_multipliers() {
+        random::seed(Some(3));
+        let rules = Rules {
+            scoring: Scoring {
+                trump_multipliers: [1, 2, 1, 3],
+                no_trump_multiplier: 2,
+            },
+            ..Rules::default()
+        };
+        let mut players = random_players();
+
+        let mut game = Game::new(rules);
+        game.play(&mut players);
+
+        let mut totals = [0; 2];
+        for summary in game.scoreboard.history() {
+            let team = summary.winning_team;
+            // the stake of a round is 1, 2 or 4
+            let stake = summary.multiplier / rules.scoring.multiplier(summary.trump);
+            assert!([1, 2, 4].contains(&stake));
+            assert!(summary.points == (summary.card_scores[team] - 30) * summary.multiplier);
+            totals[team] += summary.points;
+        }
+        assert!(totals == game.scoreboard.scores());
+    }
+
+    #[test]
+    fn test_rule_presets() {
+        for (_, rules) in Rules::PRESETS {
+            let mut players = random_players();
+
+            let mut game = Game::new(rules);
+            game.play(&mut players);
 
-        let mut game = Game::new(players);
-        while !game.is_terminal() {
-            game.play_round();
+            assert!(game.scoreboard.scores()[game.winner()] >= rules.target_score);
         }
     }
 }
@@ -1,15 +1,28 @@
 use std::fmt::Debug;
 
-use crate::{action::Action, card::Card, round::Round, suit::Suit};
+use crate::{action::Action, card::Card, params::Params, round::Round, stack::Stack, suit::Suit};
 
+/// what each player is thought to hold. The weights are guesses from the way
+/// they play, the excluded cards are certain from the rules, e.g. a player that
+/// doesn't follow is void in that suit
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Inference {
     players: [CardLikelihood; 4],
+    excluded: [Stack; 4],
+    params: Params,
 }
 
 impl ismcts::inference::Inference for Inference {}
 
 impl Inference {
+    /// an inference with other factors than the hand picked ones
+    pub fn new(params: Params) -> Self {
+        Inference {
+            params,
+            ..Default::default()
+        }
+    }
+
     pub fn weights(&self, player: usize) -> [f32; 32] {
         self.players[player].l
     }
@@ -18,10 +31,36 @@ impl Inference {
         self.players[player].l[card.get_index() as usize]
     }
 
+    /// false if the player certainly doesn't hold the card
+    pub const fn can_have(&self, player: usize, card: Card) -> bool {
+        !self.excluded[player].has_card(card)
+    }
+
+    /// the cards the player certainly doesn't hold
+    #[cfg(test)]
+    pub const fn excluded(&self, player: usize) -> Stack {
+        self.excluded[player]
+    }
+
+    pub fn is_void(&self, player: usize, suit: Suit, state: &Round) -> bool {
+        let cards = state.unplayed_cards().of_suit(suit);
+        self.excluded[player] & cards == cards
+    }
+
+    fn exclude(&mut self, player: usize, cards: Stack) {
+        self.excluded[player] |= cards;
+        for card in cards.into_iter() {
+            self.players[player].remove_card(card);
+        }
+    }
+
     pub fn infer(&mut self, state: &Round, action: Action, player: usize) {
         match action {
             Action::PlayCard(card) => self.infer_card(state, player, card),
             Action::PickTrump(trump) => self.infer_trump(state, player, trump),
+            Action::PassTrump => self.infer_pass(state, player),
+            Action::Double | Action::Redouble => self.infer_double(state, player),
+            Action::Pass => {}
         }
 
         for player in &mut self.players {
@@ -38,34 +77,26 @@ impl Inference {
         if let Some(suit) = state.suit_to_follow() {
             if card.suit() != suit {
                 followed = false;
-                for card in state.unplayed_cards().of_suit(suit).into_iter() {
-                    self.players[player].remove_card(card);
-                }
+                self.exclude(player, state.unplayed_cards().of_suit(suit));
             }
         }
 
         if let Some((winning_card, winning_player)) = state.trick_ref().winner() {
             let is_losing_trick = winning_player % 2 != player % 2;
+            let rules = state.rules();
+            let must_buy = rules.must_buy && (is_losing_trick || rules.partner_must_buy);
 
-            // if the player is losing the trick and follows without buying,
+            // if the player has to buy and follows without buying,
             // that means they have no higher cards of that suit
-            if is_losing_trick
-                && winning_card.suit() == card.suit()
-                && winning_card.value() > card.value()
+            if must_buy && winning_card.suit() == card.suit() && winning_card.value() > card.value()
             {
-                for card in state
-                    .unplayed_cards()
-                    .of_suit(card.suit())
-                    .above(winning_card)
-                    .into_iter()
-                {
-                    self.players[player].remove_card(card);
-                }
+                let cards = state.unplayed_cards().of_suit(card.suit());
+                self.exclude(player, cards.above(winning_card));
             }
 
             // likewise, if the player can't follow and doesn't
             // play a trump when no trump has been played yet, that means they're void of trumps
-            if is_losing_trick
+            if must_buy
                 && !followed
                 && winning_card.suit() != card.suit()
                 && state
@@ -73,10 +104,7 @@ impl Inference {
                     .is_some_and(|trump| card.suit() != trump && winning_card.suit() != trump)
             {
                 let trump = state.trump().unwrap();
-
-                for card in state.unplayed_cards().of_suit(trump).into_iter() {
-                    self.players[player].remove_card(card);
-                }
+                self.exclude(player, state.unplayed_cards().of_suit(trump));
             }
 
             // if the player is losing the trick and does not buy,
@@ -94,26 +122,42 @@ impl Inference {
                 };
 
                 for card in cards.below(card).into_iter() {
-                    self.players[player].scale(card, 0.7);
+                    self.players[player].scale(card, self.params.low_card);
                 }
             }
 
             // if the player doesn't follow and the current winning card is a trump, which they don't beat,
             // than they don't have any higher trumps than the winning card
-            if is_losing_trick
+            if must_buy
                 && !followed
                 && state
                     .trump()
                     .is_some_and(|trump| card.suit() != trump && winning_card.suit() == trump)
             {
-                for card in state
-                    .unplayed_cards()
-                    .of_suit(winning_card.suit()) // i.e. trump
-                    .above(winning_card)
-                    .into_iter()
-                {
-                    self.players[player].remove_card(card);
-                }
+                // i.e. trump
+                let cards = state.unplayed_cards().of_suit(winning_card.suit());
+                self.exclude(player, cards.above(winning_card));
+            }
+        }
+    }
+
+    /// the dealer only passes when they don't have a strong suit,
+    /// so they're less likely to hold aces and tens
+    fn infer_pass(&mut self, state: &Round, player: usize) {
+        self.players[player].scale_high_cards(state.unplayed_cards(), 6, self.params.pass);
+    }
+
+    /// a player that doubles likely holds the high trumps,
+    /// or the high cards in general when playing without trump
+    fn infer_double(&mut self, state: &Round, player: usize) {
+        let cards = match state.trump() {
+            Some(trump) => state.unplayed_cards().of_suit(trump),
+            None => state.unplayed_cards(),
+        };
+
+        for (i, p) in self.players.iter_mut().enumerate() {
+            if i != player {
+                p.scale_high_cards(cards, 5, self.params.double);
             }
         }
     }
@@ -122,7 +166,7 @@ impl Inference {
         if let Some(suit) = trump {
             for (i, p) in self.players.iter_mut().enumerate() {
                 for card in state.unplayed_cards().of_suit(suit).into_iter() {
-                    let prob = (card.value() as f32 + 5.) / 12. * 0.7;
+                    let prob = (card.value() as f32 + 5.) / 12. * self.params.trump_prior;
                     if i == player {
                         p.set_if_has(card, prob);
                     } else {
@@ -158,6 +202,15 @@ impl CardLikelihood {
         self.l[card.get_index() as usize] *= factor;
     }
 
+    /// scales the `cards` with at least `min_value`, e.g. 6 for the aces and tens
+    fn scale_high_cards(&mut self, cards: Stack, min_value: u16, factor: f32) {
+        for card in cards.into_iter() {
+            if card.value() >= min_value {
+                self.scale(card, factor);
+            }
+        }
+    }
+
     fn remove_card(&mut self, card: Card) {
         self.l[card.get_index() as usize] = 0.;
     }
@@ -181,3 +234,60 @@ impl Debug for CardLikelihood {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::Inference;
+    use crate::{action::Action, random, round::Round, stack::Stack, suit::Suit};
+
+    #[test]
+    fn test_samples_obey_play() {
+        random::seed(Some(12));
+
+        for _ in 0..30 {
+            let mut round = Round::new(0);
+            let mut inference = Inference::default();
+            // the suits each player failed to follow
+            let mut voids: [Vec<Suit>; 4] = Default::default();
+
+            while !round.is_terminal() {
+                let action = round.possible_actions().pop_random().unwrap();
+                let player = round.turn();
+                if let (Action::PlayCard(card), Some(suit)) = (action, round.suit_to_follow()) {
+                    if card.suit() != suit {
+                        voids[player].push(suit);
+                    }
+                }
+                inference.infer(&round, action, player);
+                round.apply_action(action);
+
+                for (player, suits) in voids.iter().enumerate() {
+                    // the constraints are facts about the actual deal
+                    assert_eq!(round.player_cards(player) & inference.excluded(player), 0);
+                    for &suit in suits {
+                        assert!(inference.is_void(player, suit, &round));
+                    }
+                }
+
+                let observer = round.turn();
+                for _ in 0..3 {
+                    let sample = round.randomize(observer, &inference);
+                    let mut dealt = Stack::default();
+
+                    for (player, suits) in voids.iter().enumerate() {
+                        let hand = sample.player_cards(player);
+                        assert_eq!(hand.len(), round.player_cards(player).len());
+                        assert_eq!(hand & inference.excluded(player), 0);
+                        assert!(suits.iter().all(|&suit| !hand.has_suit(suit)));
+                        dealt |= hand;
+                    }
+
+                    assert!(sample.player_cards(observer) == round.player_cards(observer));
+                    assert!(dealt == !round.played_cards());
+                }
+            }
+        }
+    }
+}
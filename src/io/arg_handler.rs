@@ -1,17 +1,31 @@
-use crate::players::mcts_player::MctsPlayer;
-use crate::players::random_player::RandomPlayer;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::bench::bench;
+use crate::calibration::run_calibration;
+use crate::league::run_league;
+use crate::players::spec::PlayerSpec;
 use crate::players::PlayerVec;
-use crate::sprt::run_sprt;
-use crate::tournament::run_tournament_multithreaded;
-use crate::{bench::bench, players::Player};
+use crate::random;
+use crate::rules::Rules;
+use crate::sprt::{run_sprt, SprtConfig};
+use crate::spsa::{run_spsa, SpsaConfig};
+use crate::tournament::{run_tournament_multithreaded, TournamentOptions};
 
 use super::debugger::Debugger;
 use super::input;
 
-pub fn handle_args(args: Vec<String>) {
+/// runs the requested tools and returns the rules and players to play the gui with.
+/// Every tool takes the players from `--team0` and `--team1`, which set both seats
+/// of a team, and `--seat0` to `--seat3`, e.g. `--team1 mcts:sims=5000,inference=off`
+pub fn handle_args(args: Vec<String>) -> (Rules, [PlayerSpec; 4]) {
+    random::seed(parse_seed(&args));
+    let rules = parse_rules(&args);
+
     if args.contains(&"bench".to_owned()) {
         let size = input::read_parsed("number of games: ").ok();
-        bench(size);
+        let players = parse_players(&args, ["random", "random"]);
+        bench(size, rules, &players_name(&players), player_gen(players));
     }
 
     if args.contains(&"d".to_owned()) {
@@ -20,32 +34,165 @@ pub fn handle_args(args: Vec<String>) {
     }
 
     if args.contains(&"sprt".to_owned()) {
-        let think_time = input::read_parsed("think time: ").unwrap_or(100);
-        let player_gen = move || -> PlayerVec {
-            vec![
-                Box::new(MctsPlayer::new(think_time, true)),
-                Box::new(MctsPlayer::new(think_time, false)),
-                Box::new(MctsPlayer::new(think_time, true)),
-                Box::new(MctsPlayer::new(think_time, false)),
-            ]
-        };
-        run_sprt(14, player_gen);
+        let players = parse_players(
+            &args,
+            ["mcts:time=100,inference=on", "mcts:time=100,inference=off"],
+        );
+        println!("{}", players_name(&players));
+        let config = parse_sprt_config(&args);
+        run_sprt(14, rules, config, player_gen(players));
     }
 
     if args.contains(&"tournament".to_owned()) {
         let num_games = input::read_parsed("games per thread: ").unwrap_or(5);
         let num_threads = input::read_parsed("threads: ").unwrap_or(14);
-        let think_time = input::read_parsed("think time: ").unwrap_or(100);
-
-        let player_gen = move || -> PlayerVec {
-            vec![
-                RandomPlayer::boxed(),
-                Box::new(MctsPlayer::new(think_time, true)),
-                RandomPlayer::boxed(),
-                Box::new(MctsPlayer::new(think_time, true)),
-            ]
+        let players = parse_players(&args, ["random", "mcts:time=100"]);
+
+        let options = TournamentOptions {
+            verbose: false,
+            duplicate: args.contains(&"--duplicate".to_owned()),
+            records_dir: arg_value(&args, "--records").map(Path::new),
         };
+        run_tournament_multithreaded(
+            num_games,
+            num_threads,
+            &players_name(&players),
+            rules,
+            options,
+            player_gen(players),
+        );
+    }
+
+    if args.contains(&"league".to_owned()) {
+        let players = parse_league_players(&args);
+        let num_pairs = input::read_parsed("deals per matchup: ").unwrap_or(50);
+        let num_threads = input::read_parsed("threads: ").unwrap_or(14);
+        run_league(&players, num_pairs, num_threads, rules);
+    }
+
+    if args.contains(&"calibrate".to_owned()) {
+        let num_rounds = input::read_parsed("rounds: ").unwrap_or(200);
+        let players = parse_players(&args, ["heuristic", "heuristic"]);
+        println!("{}", players_name(&players));
+        run_calibration(num_rounds, rules, player_gen(players));
+    }
+
+    if args.contains(&"spsa".to_owned()) {
+        let player = parse_players(&args, ["mcts:sims=1000", "mcts:sims=1000"])[0];
+        if !matches!(player, PlayerSpec::Mcts { .. }) {
+            eprintln!("only mcts players can be tuned, got '{player}'");
+            std::process::exit(1);
+        }
+
+        let config = SpsaConfig {
+            num_iterations: input::read_parsed("iterations: ").unwrap_or(200),
+            num_pairs: input::read_parsed("pairs per iteration: ").unwrap_or(14),
+            num_threads: input::read_parsed("threads: ").unwrap_or(14),
+        };
+        let output = arg_value(&args, "--params").unwrap_or("params.txt");
+        run_spsa(player, rules, config, Path::new(output));
+    }
+
+    (rules, parse_players(&args, ["mcts", "mcts"]))
+}
+
+/// the value following `name`, e.g. `--rules walloon`
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(|value| value.as_str())
+}
+
+/// parses the value following `name`, exits with an error if it is invalid
+fn parsed_arg<T: FromStr>(args: &[String], name: &str, expected: &str) -> Option<T> {
+    let value = arg_value(args, name)?;
+
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("invalid {name} '{value}', expected {expected}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_seed(args: &[String]) -> Option<u64> {
+    parsed_arg(args, "--seed", "a positive integer")
+}
 
-        run_tournament_multithreaded(num_games, num_threads, "mcts vs random", false, player_gen);
+/// the players for each seat, `defaults` are the specs of both teams
+fn parse_players(args: &[String], defaults: [&str; 2]) -> [PlayerSpec; 4] {
+    const EXPECTED: &str = "a player like random or mcts:time=200,inference=on";
+
+    let teams = [0, 1].map(|team| {
+        parsed_arg(args, &format!("--team{team}"), EXPECTED)
+            .unwrap_or_else(|| defaults[team].parse().unwrap())
+    });
+
+    std::array::from_fn(|seat| {
+        parsed_arg(args, &format!("--seat{seat}"), EXPECTED).unwrap_or(teams[seat % 2])
+    })
+}
+
+/// the players following `league`, e.g. `league random mcts:time=50 mcts:sims=2000`
+fn parse_league_players(args: &[String]) -> Vec<PlayerSpec> {
+    let players = args
+        .iter()
+        .skip_while(|arg| *arg != "league")
+        .skip(1)
+        .take_while(|arg| !arg.starts_with("--"))
+        .map(|arg| {
+            arg.parse().unwrap_or_else(|e| {
+                eprintln!("invalid player '{arg}': {e}");
+                std::process::exit(1);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if players.len() < 2 {
+        eprintln!("a league needs at least 2 players, e.g. league random mcts:time=50");
+        std::process::exit(1);
     }
+
+    players
+}
+
+fn players_name(players: &[PlayerSpec; 4]) -> String {
+    if players[0] == players[2] && players[1] == players[3] {
+        format!("{} vs {}", players[0], players[1])
+    } else {
+        format!(
+            "{} & {} vs {} & {}",
+            players[0], players[2], players[1], players[3]
+        )
+    }
+}
+
+fn player_gen(players: [PlayerSpec; 4]) -> impl Fn() -> PlayerVec + Clone + Send + Sync {
+    move || players.iter().map(PlayerSpec::build).collect()
+}
+
+/// e.g. `--elo0 0 --elo1 10 --alpha 0.05 --beta 0.05`
+fn parse_sprt_config(args: &[String]) -> SprtConfig {
+    let default = SprtConfig::default();
+
+    SprtConfig {
+        elo0: parsed_arg(args, "--elo0", "a number").unwrap_or(default.elo0),
+        elo1: parsed_arg(args, "--elo1", "a number").unwrap_or(default.elo1),
+        alpha: parsed_arg(args, "--alpha", "a probability").unwrap_or(default.alpha),
+        beta: parsed_arg(args, "--beta", "a probability").unwrap_or(default.beta),
+    }
+}
+
+fn parse_rules(args: &[String]) -> Rules {
+    let Some(name) = arg_value(args, "--rules") else {
+        return Rules::default();
+    };
+
+    Rules::preset(name).unwrap_or_else(|| {
+        let presets = Rules::PRESETS.map(|(name, _)| name);
+        eprintln!("unknown rules '{name}', expected one of {presets:?}");
+        std::process::exit(1);
+    })
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
+use std::path::Path;
 
-use ismcts::state::State;
+use ismcts::{action_list::ActionList, state::State};
 
 use crate::action::Action;
 use crate::action_collection::ActionCollection;
@@ -8,8 +9,12 @@ use crate::inference::Inference;
 use crate::io::input;
 use crate::players::mcts_player::MctsPlayer;
 use crate::players::Player;
+use crate::record::{GameRecord, RoundRecord};
 use crate::round::{Round, RoundPhase};
-use crate::stack::Stack;
+use crate::rules::Rules;
+use crate::search::searcher::Budget;
+use crate::solver::Solver;
+use crate::suit::Suit;
 
 struct Command {
     name: char,
@@ -17,11 +22,20 @@ struct Command {
     task: fn(&mut Debugger),
 }
 
+/// a recorded round that is being stepped through
+struct Replay {
+    round: RoundRecord,
+    rules: Rules,
+    /// number of recorded actions that have been applied
+    index: usize,
+}
+
 pub struct Debugger {
     commands: HashMap<char, Command>,
     state: Round,
     inference: Inference,
     player: MctsPlayer,
+    replay: Option<Replay>,
 }
 
 impl Debugger {
@@ -31,29 +45,46 @@ impl Debugger {
             state: Round::new(romu::range_usize(0..4)),
             inference: Default::default(),
             player: MctsPlayer::new(1000, true),
+            replay: None,
         };
         debugger.add_command(Command {
             name: '+',
-            description: "increase search time by 100ms".to_owned(),
+            description: "increase search budget by 100ms or 1000 simulations".to_owned(),
             task: |d| {
-                let prev_time = d.player.get_search_time();
-                d.player.set_search_time(prev_time + 100);
+                let budget = match d.player.get_budget() {
+                    Budget::Time(time) => Budget::Time(time + 100),
+                    Budget::Simulations(num_sims) => Budget::Simulations(num_sims + 1000),
+                };
+                d.player.set_budget(budget);
             },
         });
         debugger.add_command(Command {
             name: '-',
-            description: "decrease search time by 100ms".to_owned(),
+            description: "decrease search budget by 100ms or 1000 simulations".to_owned(),
             task: |d| {
-                let prev_time = d.player.get_search_time();
-                d.player.set_search_time(prev_time - 100);
+                let budget = match d.player.get_budget() {
+                    Budget::Time(time) => Budget::Time(time.saturating_sub(100)),
+                    Budget::Simulations(num_sims) => {
+                        Budget::Simulations(num_sims.saturating_sub(1000))
+                    }
+                };
+                d.player.set_budget(budget);
             },
         });
         debugger.add_command(Command {
             name: 't',
-            description: "print current search time".to_owned(),
+            description: "print current search budget".to_owned(),
             task: |d| {
-                let time = d.player.get_search_time();
-                println!("current search time: {time}");
+                let budget = d.player.get_budget();
+                println!("current search budget: {budget}");
+            },
+        });
+        debugger.add_command(Command {
+            name: 'b',
+            description: "read a search budget, e.g. 500ms or 20000sims".to_owned(),
+            task: |d| match input::read_parsed::<Budget>("budget: ") {
+                Ok(budget) => d.player.set_budget(budget),
+                Err(e) => println!("{e}"),
             },
         });
         debugger.add_command(Command {
@@ -69,6 +100,15 @@ impl Debugger {
             task: |d| {
                 dbg!(&d.state);
                 dbg!(&d.inference);
+
+                let suits = [Suit::Spades, Suit::Clubs, Suit::Hearts, Suit::Diamonds];
+                for player in 0..4 {
+                    let voids = suits
+                        .into_iter()
+                        .filter(|&suit| d.inference.is_void(player, suit, &d.state))
+                        .collect::<Vec<_>>();
+                    println!("player {player} is void in {voids:?}");
+                }
             },
         });
         debugger.add_command(Command {
@@ -100,20 +140,49 @@ impl Debugger {
             },
         });
         debugger.add_command(Command {
-            name: 'f',
-            description: "todo".to_owned(),
+            name: 'o',
+            description: "open a game record and replay one of its rounds".to_owned(),
             task: |d| {
-                let actions = match d.state.phase() {
-                    RoundPhase::PickTrump => ActionCollection::Trumps(0b11111),
-                    RoundPhase::PlayCards => {
-                        ActionCollection::Cards(Stack::ALL ^ d.state.played_cards())
+                println!("path: ");
+                let path = input::read_line();
+                let record = match GameRecord::load(Path::new(path.trim())) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        println!("{e}");
+                        return;
                     }
                 };
-                let actions = request_action(actions);
-                for action in actions {
-                    // d.state = d
-                    //     .state
-                    //     .observe_action(observer.unwrap(), action, &d.inference);
+
+                println!("{record}");
+                let index = input::read_parsed::<usize>("round (1): ").unwrap_or(1);
+                let Some(round) = record.rounds.get(index.wrapping_sub(1)) else {
+                    println!("the record only has {} rounds", record.rounds.len());
+                    return;
+                };
+
+                d.replay = Some(Replay {
+                    round: round.clone(),
+                    rules: record.rules,
+                    index: 0,
+                });
+                d.replay_to(0);
+            },
+        });
+        debugger.add_command(Command {
+            name: '>',
+            description: "step forward through the opened record".to_owned(),
+            task: |d| {
+                if let Some(replay) = &d.replay {
+                    d.replay_to(replay.index + 1);
+                }
+            },
+        });
+        debugger.add_command(Command {
+            name: '<',
+            description: "step back through the opened record".to_owned(),
+            task: |d| {
+                if let Some(replay) = &d.replay {
+                    d.replay_to(replay.index.saturating_sub(1));
                 }
             },
         });
@@ -134,17 +203,39 @@ impl Debugger {
                     println!("operation aborted! should select only one action");
                 } else {
                     let action = actions[0];
+                    d.player.observe(&d.state, action);
                     d.inference.infer(&d.state, action, d.state.turn());
                     d.state.apply_action(action);
                 }
             },
         });
+        debugger.add_command(Command {
+            name: 's',
+            description: "solve the current round with all cards known".to_owned(),
+            task: |d| {
+                if d.state.phase() != RoundPhase::PlayCards || d.state.is_terminal() {
+                    println!("can only solve while playing cards");
+                    return;
+                }
+
+                let mut solver = Solver::default();
+                let solution = solver.solve(&d.state);
+                println!(
+                    "best possible score for player {}'s team is {}, by playing {}",
+                    d.state.turn(),
+                    solution.score,
+                    solution.best_card
+                );
+                println!("searched {} positions", solver.num_nodes());
+            },
+        });
         debugger.add_command(Command {
             name: 'a',
             description: "let mcts select the current player's action".to_owned(),
             task: |d| {
                 let action = d.player.decide(d.state, &d.inference);
                 println!("player {} plays {action:?}\n", d.state.turn());
+                d.player.observe(&d.state, action);
                 d.inference.infer(&d.state, action, d.state.turn());
                 d.state.apply_action(action);
             },
@@ -169,6 +260,37 @@ impl Debugger {
         }
     }
 
+    /// replay the opened record from the start, up to and excluding the action at `index`
+    fn replay_to(&mut self, index: usize) {
+        let replay = self.replay.as_mut().unwrap();
+        let index = index.min(replay.round.actions.len());
+        let round = &replay.round;
+
+        self.state = Round::from_deal(round.dealer, round.hands, replay.rules);
+        self.inference = Inference::default();
+        replay.index = index;
+
+        for (i, &action) in round.actions[..index].iter().enumerate() {
+            if !self.state.possible_actions().has(&action) {
+                println!("illegal action {action} in record, stopping");
+                replay.index = i;
+                return;
+            }
+
+            self.inference.infer(&self.state, action, self.state.turn());
+            self.state.apply_action(action);
+        }
+
+        match round.actions.get(index) {
+            Some(action) => println!(
+                "{index}/{}: player {} plays {action}",
+                round.actions.len(),
+                self.state.turn()
+            ),
+            None => println!("end of round, scores: {:?}", self.state.scores()),
+        }
+    }
+
     fn add_command(&mut self, command: Command) {
         self.commands.insert(command.name, command);
     }
@@ -17,14 +17,17 @@ pub fn read_round() -> Round {
     };
 
     let mut dealer = 0;
+    let mut trump_chooser = 0;
+    let mut doublings = 0;
     let mut turn = 1;
     let mut played_cards = Stack::default();
     let mut player_card_counts = [8; 4];
     let mut trump = None;
     let mut scores = [0; 2];
 
-    if phase == RoundPhase::PickTrump {
+    if phase != RoundPhase::PlayCards {
         dealer = read_parsed("dealer (0): ").unwrap_or(dealer);
+        trump_chooser = dealer;
         turn = (dealer + 1) % 4;
     }
     if phase == RoundPhase::PlayCards {
@@ -44,6 +47,8 @@ pub fn read_round() -> Round {
         scores = read_vec_parsed("scores ([0, 0]): ")
             .try_into()
             .unwrap_or(scores);
+        trump_chooser = read_parsed("trump chooser (0): ").unwrap_or(trump_chooser);
+        doublings = read_parsed("doublings (0): ").unwrap_or(doublings);
 
         turn = read_parsed("turn (1): ").unwrap_or(turn);
     }
@@ -53,6 +58,8 @@ pub fn read_round() -> Round {
         played_cards,
         player_card_counts,
         dealer,
+        trump_chooser,
+        doublings,
         turn,
         phase,
         trump,
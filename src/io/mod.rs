@@ -1,3 +1,3 @@
 pub mod arg_handler;
 mod debugger;
-mod input;
\ No newline at end of file
+mod input;
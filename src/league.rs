@@ -0,0 +1,170 @@
+//! round-robin league between player configurations. Every configuration plays
+//! both seats of a team against every other one, on duplicate deals, and the
+//! results are turned into elo ratings with a Bradley-Terry model
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use crate::{players::spec::PlayerSpec, random, rules::Rules, tournament::play_pair};
+
+/// plays `num_pairs` duplicate pairs for every matchup and prints a rating table
+pub fn run_league(players: &[PlayerSpec], num_pairs: usize, num_threads: usize, rules: Rules) {
+    let n = players.len();
+    let matchups = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .flat_map(|matchup| std::iter::repeat_n(matchup, num_pairs))
+        .collect::<Vec<_>>();
+
+    println!(
+        "starting league of {n} players with {} games on {num_threads} threads",
+        2 * matchups.len()
+    );
+
+    // wins[a][b] is the number of games a won against b
+    let wins = Arc::new(Mutex::new(vec![vec![0; n]; n]));
+    let next_matchup = Arc::new(AtomicUsize::new(0));
+
+    std::thread::scope(|s| {
+        for _ in 0..num_threads {
+            let wins = Arc::clone(&wins);
+            let next_matchup = Arc::clone(&next_matchup);
+            let matchups = &matchups;
+            let seed = random::thread_seed();
+
+            s.spawn(move || {
+                random::seed(Some(seed));
+                while let Some(&(a, b)) = matchups.get(next_matchup.fetch_add(1, Ordering::Relaxed))
+                {
+                    let specs = [players[a], players[b], players[a], players[b]];
+                    let player_gen = || specs.iter().map(PlayerSpec::build).collect();
+
+                    for (game, team) in play_pair(rules, false, &player_gen) {
+                        let mut wins = wins.lock().unwrap();
+                        if game.winner() == team {
+                            wins[a][b] += 1;
+                        } else {
+                            wins[b][a] += 1;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let wins = wins.lock().unwrap();
+    print_table(players, &wins, &ratings(&wins));
+}
+
+/// maximum likelihood elo and its 95% error margin for every player, with the
+/// mean rating at 0. A virtual draw against every opponent keeps the ratings
+/// of players without wins or losses finite
+fn ratings(wins: &[Vec<usize>]) -> Vec<(f64, f64)> {
+    let n = wins.len();
+    let games = |a: usize, b: usize| {
+        if a == b || wins[a][b] + wins[b][a] == 0 {
+            0.
+        } else {
+            (wins[a][b] + wins[b][a]) as f64 + 1.
+        }
+    };
+    let score = |a: usize| -> f64 {
+        (0..n)
+            .filter(|&b| games(a, b) > 0.)
+            .map(|b| wins[a][b] as f64 + 0.5)
+            .sum()
+    };
+
+    // minorization-maximization on the strengths of the players
+    let mut strengths = vec![1.; n];
+    for _ in 0..10_000 {
+        let mut next = (0..n)
+            .map(|a| {
+                let denominator: f64 = (0..n)
+                    .map(|b| games(a, b) / (strengths[a] + strengths[b]))
+                    .sum();
+                if denominator == 0. {
+                    1.
+                } else {
+                    score(a) / denominator
+                }
+            })
+            .collect::<Vec<f64>>();
+
+        let mean = next.iter().map(|s| s.ln()).sum::<f64>() / n as f64;
+        next.iter_mut().for_each(|s| *s /= mean.exp());
+
+        let change = next
+            .iter()
+            .zip(&strengths)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0., f64::max);
+        strengths = next;
+
+        if change < 1e-9 {
+            break;
+        }
+    }
+
+    let to_elo = 400. / 10f64.ln();
+    (0..n)
+        .map(|a| {
+            let information: f64 = (0..n)
+                .map(|b| {
+                    let p = strengths[a] / (strengths[a] + strengths[b]);
+                    games(a, b) * p * (1. - p)
+                })
+                .sum();
+
+            (
+                to_elo * strengths[a].ln(),
+                1.96 * to_elo / information.sqrt(),
+            )
+        })
+        .collect()
+}
+
+fn print_table(players: &[PlayerSpec], wins: &[Vec<usize>], ratings: &[(f64, f64)]) {
+    let mut order = (0..players.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| ratings[b].0.total_cmp(&ratings[a].0));
+
+    println!("rank\telo\t±95%\tgames\tscore\tplayer");
+    for (rank, &a) in order.iter().enumerate() {
+        let num_wins: usize = wins[a].iter().sum();
+        let num_losses: usize = wins.iter().map(|row| row[a]).sum();
+        let num_games = num_wins + num_losses;
+
+        println!(
+            "{}\t{:.0}\t{:.0}\t{num_games}\t{:.1}%\t{}",
+            rank + 1,
+            ratings[a].0,
+            ratings[a].1,
+            100. * num_wins as f64 / num_games as f64,
+            players[a]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ratings;
+
+    #[test]
+    fn test_ratings() {
+        // a scores 75% against b, b scores 75% against c
+        let wins = vec![vec![0, 300, 0], vec![100, 0, 300], vec![0, 100, 0]];
+        let elos = ratings(&wins);
+
+        assert!(elos.iter().map(|(elo, _)| elo).sum::<f64>().abs() < 1e-6);
+        // 75% is a difference of about 190 elo
+        assert!((elos[0].0 - elos[1].0 - 190.).abs() < 5.);
+        assert!((elos[1].0 - elos[2].0 - 190.).abs() < 5.);
+        // b played twice as many games
+        assert!(elos[1].1 < elos[0].1);
+
+        let wins = vec![vec![0, 10], vec![0, 0]];
+        assert!(ratings(&wins)
+            .iter()
+            .all(|(elo, margin)| elo.is_finite() && margin.is_finite()));
+    }
+}
@@ -6,13 +6,25 @@ mod action_collection;
 mod array;
 mod bench;
 mod bits;
+mod calibration;
 mod card;
 mod game;
 mod inference;
 mod io;
+mod league;
+mod params;
 mod players;
+mod random;
+mod record;
 mod round;
+mod rules;
+mod sampler;
+mod scoreboard;
+mod scoring;
+mod search;
+mod solver;
 mod sprt;
+mod spsa;
 mod stack;
 mod suit;
 mod tournament;
@@ -21,10 +33,9 @@ mod ui;
 
 #[macroquad::main("main")]
 async fn main() {
-    romu::seed();
     let args: Vec<String> = std::env::args().collect();
-    handle_args(args);
+    let (rules, players) = handle_args(args);
 
-    let mut app = App::new().await;
+    let mut app = App::new(rules, players).await;
     app.run().await;
 }
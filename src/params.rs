@@ -0,0 +1,187 @@
+//! the constants of the search and the inference that can be tuned, see
+//! [`crate::spsa`]. They're saved as one `name = value` line each:
+//!
+//! ```text
+//! exploration = 1.41421
+//! low_card = 0.7
+//! ```
+//!
+//! Missing names keep their default, so older files still load
+use std::{fmt::Display, path::Path, str::FromStr};
+
+/// a constant that can be tuned, with the range it's kept in
+pub struct Tunable {
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    /// how far the tuner perturbs it at first
+    pub step: f32,
+}
+
+pub const TUNABLES: [Tunable; 5] = [
+    Tunable {
+        name: "exploration",
+        min: 0.1,
+        max: 16.,
+        step: 0.5,
+    },
+    Tunable {
+        name: "low_card",
+        min: 0.05,
+        max: 1.,
+        step: 0.05,
+    },
+    Tunable {
+        name: "pass",
+        min: 0.05,
+        max: 1.,
+        step: 0.05,
+    },
+    Tunable {
+        name: "double",
+        min: 0.05,
+        max: 1.,
+        step: 0.05,
+    },
+    Tunable {
+        name: "trump_prior",
+        min: 0.05,
+        max: 1.,
+        step: 0.05,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    /// the exploration constant of the uct formula, relative to the rewards of a
+    /// round that isn't doubled and has the lowest multiplier. The rewards are
+    /// scaled down to keep the highest stakes within 1, and so is this
+    pub exploration: f32,
+    /// how much less likely a player losing a trick holds cards below the one they played
+    pub low_card: f32,
+    /// how much less likely the dealer holds aces and tens after passing trump
+    pub pass: f32,
+    /// how much less likely the other players hold the high cards after a double
+    pub double: f32,
+    /// the chance that the trump chooser holds the ace of trump, lower cards are less likely
+    pub trump_prior: f32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            exploration: 2f32.sqrt(),
+            low_card: 0.7,
+            pass: 0.7,
+            double: 0.7,
+            trump_prior: 0.7,
+        }
+    }
+}
+
+impl Params {
+    /// the value of `TUNABLES[index]`
+    pub fn value(&self, index: usize) -> f32 {
+        [
+            self.exploration,
+            self.low_card,
+            self.pass,
+            self.double,
+            self.trump_prior,
+        ][index]
+    }
+
+    pub fn value_mut(&mut self, index: usize) -> &mut f32 {
+        match index {
+            0 => &mut self.exploration,
+            1 => &mut self.low_card,
+            2 => &mut self.pass,
+            3 => &mut self.double,
+            4 => &mut self.trump_prior,
+            _ => panic!("no tunable {index}"),
+        }
+    }
+
+    /// sets the tunable called `name`, checking its range
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let index = TUNABLES
+            .iter()
+            .position(|tunable| tunable.name == name)
+            .ok_or_else(|| format!("unknown parameter '{name}'"))?;
+        let tunable = &TUNABLES[index];
+
+        match value.parse::<f32>() {
+            Ok(value) if (tunable.min..=tunable.max).contains(&value) => {
+                *self.value_mut(index) = value;
+                Ok(())
+            }
+            _ => Err(format!(
+                "invalid value '{value}' for {name}, expected a number from {} to {}",
+                tunable.min, tunable.max
+            )),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?
+            .parse()
+    }
+}
+
+impl Display for Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, tunable) in TUNABLES.iter().enumerate() {
+            writeln!(f, "{} = {}", tunable.name, self.value(i))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Params {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Params::default();
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("expected name = value, got '{line}'"))?;
+            params.set(name.trim(), value.trim())?;
+        }
+
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Params, TUNABLES};
+
+    #[test]
+    fn test_parse() {
+        let params = Params {
+            exploration: 0.9,
+            trump_prior: 0.55,
+            ..Default::default()
+        };
+        assert_eq!(params.to_string().parse(), Ok(params));
+
+        assert_eq!("low_card = 0.5".parse::<Params>().unwrap().low_card, 0.5);
+        assert_eq!("\n".parse(), Ok(Params::default()));
+        assert!("depth = 3".parse::<Params>().is_err());
+        assert!("low_card = 2".parse::<Params>().is_err());
+        assert!("low_card".parse::<Params>().is_err());
+
+        for (i, tunable) in TUNABLES.iter().enumerate() {
+            let value = Params::default().value(i);
+            assert!((tunable.min..=tunable.max).contains(&value));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use ismcts::{action_list::ActionList, state::State};
+
+use super::Player;
+use crate::{
+    action::Action,
+    card::Card,
+    inference::Inference,
+    round::{Round, RoundPhase},
+    stack::Stack,
+    suit::Suit,
+};
+
+const SUITS: [Suit; 4] = [Suit::Spades, Suit::Clubs, Suit::Hearts, Suit::Diamonds];
+
+/// a suit of this strength or more is worth playing as trump
+const TRUMP_STRENGTH: i16 = 10;
+/// a trump suit of this strength or more is worth doubling
+const DOUBLE_STRENGTH: i16 = 14;
+/// this many tens makes playing without trump worthwhile
+const NO_TRUMP_TENS: u32 = 3;
+
+/// a fixed set of rules of thumb, deterministic and near instant
+#[derive(Default)]
+pub struct HeuristicPlayer;
+
+impl Player for HeuristicPlayer {
+    fn decide(&mut self, round: Round, _inference: &Inference) -> Action {
+        let actions = round.possible_actions();
+        if actions.len() == 1 {
+            return actions.to_vec()[0];
+        }
+
+        match round.phase() {
+            RoundPhase::PickTrump | RoundPhase::PartnerPickTrump => pick_trump(&round),
+            RoundPhase::Double => bet(&round, Action::Double),
+            RoundPhase::Redouble => bet(&round, Action::Redouble),
+            RoundPhase::PlayCards => Action::PlayCard(play_card(&round)),
+        }
+    }
+}
+
+/// the chance that a playout move is random instead of the heuristic one,
+/// so the playouts don't all follow the same line
+const ROLLOUT_EPSILON: f32 = 0.2;
+
+/// plays `round` to the end with the heuristic moves, with some randomness
+pub fn rollout(round: &mut Round) {
+    while !round.is_terminal() {
+        let action = if romu::f32() < ROLLOUT_EPSILON {
+            round.possible_actions().pop_random().unwrap()
+        } else {
+            HeuristicPlayer.decide(*round, &Inference::default())
+        };
+        round.apply_action(action);
+    }
+}
+
+/// length counts most for a trump suit, the high cards make the rest
+fn suit_strength(hand: Stack, suit: Suit) -> i16 {
+    let cards = hand.of_suit(suit);
+    let high_cards = cards.into_iter().map(|card| card.score()).sum::<i16>();
+
+    2 * cards.len() as i16 + high_cards
+}
+
+fn pick_trump(round: &Round) -> Action {
+    let hand = round.player_cards(round.trump_chooser());
+    let (strength, suit) = SUITS
+        .iter()
+        .map(|&suit| (suit_strength(hand, suit), suit))
+        .max_by_key(|(strength, _)| *strength)
+        .unwrap();
+
+    let num_tens = SUITS
+        .iter()
+        .filter(|&&suit| hand.highest_of_suit(suit).is_some_and(|c| c.value() == 7))
+        .count() as u32;
+
+    if num_tens >= NO_TRUMP_TENS && hand.of_suit(suit).len() <= 3 {
+        Action::PickTrump(None)
+    } else if strength < TRUMP_STRENGTH && round.possible_actions().has(&Action::PassTrump) {
+        Action::PassTrump
+    } else {
+        Action::PickTrump(Some(suit))
+    }
+}
+
+/// double or redouble with a strong hand in trump, or many tens without trump
+fn bet(round: &Round, action: Action) -> Action {
+    let hand = round.player_cards(round.turn());
+    let is_strong = match round.trump() {
+        Some(trump) => suit_strength(hand, trump) >= DOUBLE_STRENGTH,
+        None => SUITS.iter().all(|&suit| {
+            hand.of_suit(suit) == 0 || hand.highest_of_suit(suit).unwrap().value() >= 6
+        }),
+    };
+
+    if is_strong {
+        action
+    } else {
+        Action::Pass
+    }
+}
+
+fn play_card(round: &Round) -> Card {
+    let mut legal = Stack::default();
+    for action in round.possible_actions().to_vec() {
+        if let Action::PlayCard(card) = action {
+            legal.push(card);
+        }
+    }
+
+    let player = round.turn();
+    let trick = round.trick_ref();
+    let trump = round.trump();
+    let is_trump = |card: &Card| Some(card.suit()) == trump;
+
+    let Some((_, winner)) = trick.winner() else {
+        return lead(round, legal);
+    };
+
+    let is_last = trick.cards().len() == 3;
+    let wins = |card: &Card| {
+        let mut trick = *trick;
+        trick.play(*card, player);
+        trick.winner().unwrap().1 == player
+    };
+
+    // the partner is winning: add points when the trick is safe, otherwise play low
+    if winner % 2 == player % 2 {
+        if is_last {
+            if let Some(card) = legal
+                .into_iter()
+                .filter(|card| card.value() < 7 && !is_trump(card))
+                .max_by_key(|card| card.score())
+            {
+                return card;
+            }
+        }
+
+        return lowest(legal, trump);
+    }
+
+    // buy as cheaply as possible, or throw away the lowest card
+    legal
+        .into_iter()
+        .filter(wins)
+        .min_by_key(|card| (is_trump(card), card.value()))
+        .unwrap_or_else(|| lowest(legal, trump))
+}
+
+/// lead a card that can't be beaten in its suit, preferring points outside trump.
+/// Otherwise lead low from the longest suit outside trump
+fn lead(round: &Round, legal: Stack) -> Card {
+    let player = round.turn();
+    let trump = round.trump();
+    let others = round.unplayed_cards() & !round.player_cards(player);
+
+    let masters = SUITS.iter().filter_map(|&suit| {
+        let card = legal.highest_of_suit(suit)?;
+        (others.of_suit(suit).above(card) == 0).then_some(card)
+    });
+    if let Some(card) = masters.max_by_key(|card| (Some(card.suit()) != trump, card.score())) {
+        return card;
+    }
+
+    let suit = SUITS
+        .iter()
+        .filter(|&&suit| legal.has_suit(suit))
+        .max_by_key(|&&suit| (Some(suit) != trump, legal.of_suit(suit).len()))
+        .unwrap();
+
+    legal.lowest_of_suit(*suit).unwrap()
+}
+
+/// the lowest card outside trump, keeping tens as long as possible
+fn lowest(cards: Stack, trump: Option<Suit>) -> Card {
+    cards
+        .into_iter()
+        .min_by_key(|card| (Some(card.suit()) == trump, card.value()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeuristicPlayer;
+    use crate::{
+        action::Action,
+        inference::Inference,
+        players::{random_player::RandomPlayer, Player, PlayerVec},
+        random,
+        round::Round,
+        rules::Rules,
+        stack::Stack,
+        suit::Suit,
+        tournament::play_pair,
+    };
+    use ismcts::state::State;
+
+    #[test]
+    fn test_play_low_for_partner() {
+        let cards = |cards: &str| {
+            let mut stack = Stack::default();
+            for card in cards.split_whitespace() {
+                stack.push(card.parse().unwrap());
+            }
+            stack
+        };
+        let hands = [
+            cards("S7 S8 S9 SJ SQ SK SA S10"),
+            cards("C7 C8 C9 CJ CQ CK CA C10"),
+            cards("H7 H8 H9 HJ HQ HK HA H10"),
+            cards("D7 D8 D9 DJ DQ DK DA D10"),
+        ];
+        let rules = Rules {
+            allow_doubling: false,
+            ..Default::default()
+        };
+
+        // player 1 leads the ten of clubs, partner 3 can't follow
+        let mut round = Round::from_deal(0, hands, rules);
+        round.apply_action(Action::PickTrump(Some(Suit::Spades)));
+        round.apply_action(Action::PlayCard("C10".parse().unwrap()));
+        round.apply_action(Action::PlayCard("H7".parse().unwrap()));
+
+        let action = HeuristicPlayer.decide(round, &Inference::default());
+        assert!(action == Action::PlayCard("D7".parse().unwrap()));
+
+        // player 0 is last and has to trump in
+        round.apply_action(action);
+        let action = HeuristicPlayer.decide(round, &Inference::default());
+        assert!(action == Action::PlayCard("S7".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_beats_random() {
+        random::seed(Some(1));
+        let player_gen = || -> PlayerVec {
+            vec![
+                HeuristicPlayer::boxed(),
+                RandomPlayer::boxed(),
+                HeuristicPlayer::boxed(),
+                RandomPlayer::boxed(),
+            ]
+        };
+
+        let num_wins = (0..20)
+            .flat_map(|_| play_pair(Rules::default(), false, &player_gen))
+            .filter(|(game, team)| game.winner() == *team)
+            .count();
+        assert!(num_wins > 30);
+    }
+}
@@ -1,17 +1,83 @@
-use std::time::Duration;
+use std::{fmt::Display, str::FromStr, sync::atomic::AtomicBool, time::Instant};
 
-use ismcts::{
-    action_list::ActionList,
-    searcher::{SearchResult, Searcher},
-    state::State,
+use ismcts::state::State;
+
+use super::{heuristic_player, Player};
+use crate::{
+    action::Action,
+    inference::Inference,
+    params::Params,
+    random,
+    round::{Round, RoundPhase},
+    search::{
+        self,
+        searcher::{share, Budget, SearchResult, Searcher},
+    },
+    solver::{card_search_result, Solver},
 };
 
-use super::Player;
-use crate::{action::Action, inference::Inference, round::Round};
+/// by default the last 3 tricks are solved exactly
+pub const SOLVE_BELOW: usize = 4;
+
+/// how the moves of a playout are picked
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rollout {
+    #[default]
+    Random,
+    /// mostly the moves of the heuristic player, which e.g. doesn't throw
+    /// tens onto a trick of the opponents
+    Heuristic,
+}
+
+impl Rollout {
+    fn policy(self) -> search::searcher::Rollout<Round> {
+        match self {
+            Rollout::Random => Round::do_rollout,
+            Rollout::Heuristic => heuristic_player::rollout,
+        }
+    }
+}
+
+impl Display for Rollout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rollout::Random => write!(f, "random"),
+            Rollout::Heuristic => write!(f, "heuristic"),
+        }
+    }
+}
+
+impl FromStr for Rollout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Rollout::Random),
+            "heuristic" => Ok(Rollout::Heuristic),
+            _ => Err(format!(
+                "unknown rollout '{s}', expected random or heuristic"
+            )),
+        }
+    }
+}
 
 pub struct MctsPlayer {
-    searcher: Searcher<Round>,
-    search_time: u128,
+    /// one searcher per thread, each with its own tree
+    searchers: Vec<Searcher<Round>>,
+    /// the nodes of all trees together, split between the searchers
+    max_nodes: Option<usize>,
+    rollout: Rollout,
+    solver: Solver,
+    budget: Budget,
+    /// solve every possible deal exactly once the player has fewer cards than this
+    solve_below: usize,
+    /// the round at the root of the search tree and the actions observed since,
+    /// to continue with the matching subtree at the next decision
+    root: Option<(Round, Vec<Action>)>,
+    params: Params,
+    /// the inference with this player's params, built from the observed actions,
+    /// together with the round it's up to date with
+    inference: Option<(Round, Inference)>,
     use_inference: bool,
     last_search_result: Option<SearchResult<Round>>,
 }
@@ -20,6 +86,10 @@ impl Player for MctsPlayer {
     fn decide(&mut self, round: Round, inference: &Inference) -> Action {
         #[cfg(not(feature = "debug"))]
         {
+            use std::time::Duration;
+
+            use ismcts::action_list::ActionList;
+
             let mut actions = round.possible_actions();
             if actions.len() == 1 {
                 self.last_search_result = Some(SearchResult {
@@ -28,20 +98,26 @@ impl Player for MctsPlayer {
                     duration: Duration::default(),
                     best_action: None,
                     child_stats: vec![],
+                    solved: false,
                 });
                 return actions.pop_random().unwrap();
             }
         }
-        let inference = if self.use_inference {
-            inference
-        } else {
-            &Inference::default()
-        };
+        let inference = &self.own_inference(&round, inference);
 
-        let result = self.searcher.search(&round, inference, self.search_time);
+        let result = match self.solve(&round, inference) {
+            Some(result) => result,
+            None => {
+                self.move_root(&round);
+                self.search(&round, inference)
+            }
+        };
         self.last_search_result = Some(result.clone());
         #[cfg(feature = "debug")]
         {
+            if result.solved {
+                println!("solved {} deals", result.num_simulations);
+            }
             println!(
                 "ran {} simulations at {} sims/sec",
                 result.num_simulations,
@@ -50,45 +126,289 @@ impl Player for MctsPlayer {
             for &(stats, action) in result.child_stats.iter() {
                 println!(
                     "{action}:\tscore={:.5},\tsims={}",
-                    stats.avg_score * 30.,
+                    round.card_score_of(stats.avg_score),
                     stats.num_sims
                 );
             }
         }
         result.best_action.unwrap()
     }
+
+    fn ponder(&mut self, player: usize, round: Round, inference: &Inference, stop: &AtomicBool) {
+        // the next decision will be solved without a tree
+        let num_cards = round.player_cards(player).len() as usize;
+        if round.phase() == RoundPhase::PlayCards && num_cards < self.solve_below {
+            return;
+        }
+
+        let inference = &self.own_inference(&round, inference);
+
+        self.move_root(&round);
+        let (first, rest) = self.searchers.split_first_mut().unwrap();
+        std::thread::scope(|s| {
+            for searcher in rest {
+                let seed = random::thread_seed();
+                s.spawn(move || {
+                    random::seed(Some(seed));
+                    searcher.ponder(&round, player, inference, stop);
+                });
+            }
+            first.ponder(&round, player, inference, stop);
+        });
+    }
+
+    fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    fn set_num_threads(&mut self, num_threads: usize) {
+        let rollout = self.rollout.policy();
+        self.searchers.resize_with(num_threads.max(1), || {
+            let mut searcher = Searcher::default();
+            searcher.set_rollout(rollout);
+            searcher
+        });
+        self.share_max_nodes();
+    }
+
+    fn observe(&mut self, round: &Round, action: Action) {
+        if let Some((_, actions)) = &mut self.root {
+            actions.push(action);
+        }
+
+        // a round starts with picking trump, after a missed action
+        // the inference of the game is used until the next round
+        let inference = match self.inference.take() {
+            _ if round.phase() == RoundPhase::PickTrump => Some(Inference::new(self.params)),
+            Some((tracked, inference)) if is_same_position(&tracked, round) => Some(inference),
+            _ => None,
+        };
+        self.inference = inference.map(|mut inference| {
+            let mut round = *round;
+            inference.infer(&round, action, round.turn());
+            round.apply_action(action);
+            (round, inference)
+        });
+    }
+
+    fn get_last_search_result(&self) -> Option<SearchResult<Round>> {
+        self.last_search_result.clone()
+    }
 }
 
 impl MctsPlayer {
     pub fn new(search_time: u128, use_inference: bool) -> Self {
+        Self::with_budget(Budget::Time(search_time), use_inference)
+    }
+
+    pub fn with_budget(budget: Budget, use_inference: bool) -> Self {
         MctsPlayer {
-            searcher: Searcher::default(),
-            search_time,
+            searchers: vec![Searcher::default()],
+            max_nodes: None,
+            rollout: Rollout::default(),
+            solver: Solver::default(),
+            budget,
+            solve_below: SOLVE_BELOW,
+            root: None,
+            params: Params::default(),
+            inference: None,
             use_inference,
             last_search_result: Default::default(),
         }
     }
 
-    pub fn set_search_time(&mut self, time: u128) {
-        self.search_time = time;
+    pub const fn get_budget(&self) -> Budget {
+        self.budget
     }
 
-    pub const fn get_search_time(&self) -> u128 {
-        self.search_time
+    /// caps the number of nodes in the trees of all threads together, which
+    /// ends a search or pondering early
+    pub fn set_max_nodes(&mut self, max_nodes: Option<usize>) {
+        self.max_nodes = max_nodes;
+        self.share_max_nodes();
     }
 
-    pub fn get_last_search_result(&self) -> Option<SearchResult<Round>> {
-        self.last_search_result.clone()
+    fn share_max_nodes(&mut self) {
+        let num_searchers = self.searchers.len();
+        for (i, searcher) in self.searchers.iter_mut().enumerate() {
+            searcher.set_max_nodes(self.max_nodes.map(|max| share(max, i, num_searchers)));
+        }
     }
+
+    pub fn set_rollout(&mut self, rollout: Rollout) {
+        self.rollout = rollout;
+        for searcher in &mut self.searchers {
+            searcher.set_rollout(rollout.policy());
+        }
+    }
+
+    /// the search and inference constants, by default the hand picked ones
+    pub fn set_params(&mut self, params: Params) {
+        self.params = params;
+        self.inference = None;
+    }
+
+    /// solve exactly once the player has fewer than `solve_below` cards left, 0 never solves
+    pub fn set_solve_below(&mut self, solve_below: usize) {
+        self.solve_below = solve_below;
+    }
+
+    /// the inference this player searches with. Its own when it saw every action of
+    /// the round, otherwise the one of the game, which uses the default params
+    fn own_inference(&self, round: &Round, inference: &Inference) -> Inference {
+        match &self.inference {
+            _ if !self.use_inference => Inference::default(),
+            Some((tracked, own)) if is_same_position(tracked, round) => *own,
+            _ => *inference,
+        }
+    }
+
+    /// keeps the subtree of the previous search if `round` follows from it
+    /// through the observed actions, otherwise starts a new tree. The exploration
+    /// is scaled along with the rewards, see [`Params::exploration`]
+    fn move_root(&mut self, round: &Round) {
+        let actions = self.root.take().and_then(|(mut root, actions)| {
+            for &action in &actions {
+                if root.is_terminal() {
+                    return None;
+                }
+                root.apply_action(action);
+            }
+            is_same_position(&root, round).then_some(actions)
+        });
+
+        let exploration = self.params.exploration / round.rules().max_multiplier() as f32;
+        for searcher in &mut self.searchers {
+            searcher.set_exploration(exploration);
+            match &actions {
+                Some(actions) => _ = searcher.advance(actions),
+                None => searcher.reset(),
+            }
+        }
+        self.root = Some((*round, vec![]));
+    }
+
+    /// searches on every thread, with independent trees for the same position
+    fn search(&mut self, round: &Round, inference: &Inference) -> SearchResult<Round> {
+        let num_threads = self.searchers.len();
+        let budget = self.budget;
+        let (first, rest) = self.searchers.split_first_mut().unwrap();
+
+        std::thread::scope(|s| {
+            let handles = rest
+                .iter_mut()
+                .enumerate()
+                .map(|(i, searcher)| {
+                    let seed = random::thread_seed();
+                    s.spawn(move || {
+                        random::seed(Some(seed));
+                        searcher.search(round, inference, budget.share(i + 1, num_threads))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let result = first.search(round, inference, budget.share(0, num_threads));
+            if handles.is_empty() {
+                return result;
+            }
+
+            let results = handles.into_iter().map(|handle| handle.join().unwrap());
+            SearchResult::merge(std::iter::once(result).chain(results))
+        })
+    }
+
+    /// the expected score of every card over all deals that are still possible,
+    /// if few enough cards are left
+    fn solve(&mut self, round: &Round, inference: &Inference) -> Option<SearchResult<Round>> {
+        let num_cards = round.player_cards(round.turn()).len() as usize;
+        if round.phase() != RoundPhase::PlayCards || num_cards >= self.solve_below {
+            return None;
+        }
+
+        let started = Instant::now();
+        let deals = round.determinizations(round.turn(), inference);
+        // the inference can rule out every deal when it got things wrong
+        if deals.is_empty() {
+            return None;
+        }
+
+        let num_deals = deals.len();
+        let scores = self.solver.expected_card_scores(deals);
+        Some(card_search_result(round, scores, num_deals, started, true))
+    }
+}
+
+fn is_same_position(a: &Round, b: &Round) -> bool {
+    a.phase() == b.phase()
+        && a.turn() == b.turn()
+        && a.played_cards() == b.played_cards()
+        && a.trump() == b.trump()
+        && a.scores() == b.scores()
+        && a.multiplier() == b.multiplier()
+        && (0..4).all(|i| a.player_cards(i) == b.player_cards(i))
 }
 
 impl Default for MctsPlayer {
     fn default() -> Self {
-        MctsPlayer {
-            searcher: Searcher::default(),
-            search_time: 500,
-            use_inference: true,
-            last_search_result: Default::default(),
-        }
+        Self::new(500, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::MctsPlayer;
+    use crate::{
+        inference::Inference, players::Player, random, round::Round, search::searcher::Budget,
+    };
+
+    #[test]
+    fn test_ponder() {
+        random::seed(Some(6));
+        let mut round = Round::new(0);
+        let action = round.possible_actions().pop_random().unwrap();
+        let mut next = round;
+        next.apply_action(action);
+        let player = next.turn();
+
+        // pondering stops at the node limit, by then every first action was tried
+        let mut bot = MctsPlayer::with_budget(Budget::Simulations(10), true);
+        bot.set_max_nodes(Some(300));
+        bot.ponder(
+            player,
+            round,
+            &Inference::default(),
+            &AtomicBool::new(false),
+        );
+
+        bot.observe(&round, action);
+        round.apply_action(action);
+        bot.decide(round, &Inference::default());
+
+        // a new tree would only hold the root and one node per simulation
+        let result = bot.get_last_search_result().unwrap();
+        assert!(result.tree_size > result.num_simulations + 1);
+    }
+
+    #[test]
+    fn test_threads() {
+        random::seed(Some(3));
+        let mut bot = MctsPlayer::with_budget(Budget::Simulations(1000), true);
+        bot.set_num_threads(4);
+        bot.decide(Round::new(0), &Inference::default());
+
+        // the simulations are divided among the threads, each with its own root
+        let result = bot.get_last_search_result().unwrap();
+        assert_eq!(result.num_simulations, 1000);
+        assert_eq!(result.tree_size, 1000 + 4);
+
+        // the node limit holds for all threads together
+        bot.set_max_nodes(Some(402));
+        bot.decide(Round::new(0), &Inference::default());
+        let result = bot.get_last_search_result().unwrap();
+        assert_eq!(result.tree_size, 402);
     }
 }
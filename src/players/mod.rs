@@ -1,11 +1,21 @@
-use crate::{action::Action, inference::Inference, round::Round};
+use std::sync::atomic::AtomicBool;
 
+use crate::{
+    action::Action,
+    inference::Inference,
+    round::Round,
+    search::searcher::{Budget, SearchResult},
+};
+
+pub mod heuristic_player;
 pub mod mcts_player;
+pub mod pimc_player;
 pub mod random_player;
+pub mod spec;
 
 pub type PlayerVec = Vec<Box<dyn Player>>;
 
-pub trait Player {
+pub trait Player: Send {
     fn boxed() -> Box<Self>
     where
         Self: Sized + Default,
@@ -14,4 +24,30 @@ pub trait Player {
     }
 
     fn decide(&mut self, round: Round, inference: &Inference) -> Action;
+
+    /// called for every action in the round, including the player's own,
+    /// before it is applied to `round`
+    fn observe(&mut self, _round: &Round, _action: Action) {}
+
+    /// think ahead while it's another player's turn, until `stop` is set.
+    /// `player` is the seat of this player
+    fn ponder(
+        &mut self,
+        _player: usize,
+        _round: Round,
+        _inference: &Inference,
+        _stop: &AtomicBool,
+    ) {
+    }
+
+    /// for players that search, how long to search from now on
+    fn set_budget(&mut self, _budget: Budget) {}
+
+    /// for players that search, how many threads to search on
+    fn set_num_threads(&mut self, _num_threads: usize) {}
+
+    /// statistics of the last decision, for players that search
+    fn get_last_search_result(&self) -> Option<SearchResult<Round>> {
+        None
+    }
 }
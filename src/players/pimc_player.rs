@@ -0,0 +1,141 @@
+use std::time::Instant;
+
+use ismcts::{action_list::ActionList, state::State};
+
+use super::{heuristic_player::HeuristicPlayer, Player};
+use crate::{
+    action::Action,
+    inference::Inference,
+    round::{Round, RoundPhase},
+    search::searcher::{Budget, SearchResult},
+    solver::{card_search_result, Solver},
+};
+
+/// perfect information monte carlo: solves sampled deals exactly and plays the
+/// card with the best average score. Picking trump and betting are left to
+/// [`HeuristicPlayer`], as those would need a solve per option for every sample
+pub struct PimcPlayer {
+    solver: Solver,
+    /// the number of samples is [`Budget::Simulations`]
+    budget: Budget,
+    use_inference: bool,
+    last_search_result: Option<SearchResult<Round>>,
+}
+
+impl Player for PimcPlayer {
+    fn decide(&mut self, round: Round, inference: &Inference) -> Action {
+        let mut actions = round.possible_actions();
+        if round.phase() != RoundPhase::PlayCards || actions.len() == 1 {
+            self.last_search_result = None;
+            return match round.phase() {
+                RoundPhase::PlayCards => actions.pop_random().unwrap(),
+                _ => HeuristicPlayer.decide(round, inference),
+            };
+        }
+
+        let inference = if self.use_inference {
+            inference
+        } else {
+            &Inference::default()
+        };
+
+        let budget = self.budget;
+        let mut num_samples = 0;
+        let started = Instant::now();
+
+        let samples = std::iter::from_fn(|| {
+            if num_samples > 0 && is_exhausted(budget, num_samples, started) {
+                return None;
+            }
+            num_samples += 1;
+            Some((round.randomize(round.turn(), inference), 1.))
+        });
+        let scores = self.solver.expected_card_scores(samples);
+
+        let result = card_search_result(&round, scores, num_samples, started, false);
+        let best_action = result.best_action.unwrap();
+        self.last_search_result = Some(result);
+
+        best_action
+    }
+
+    fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    fn get_last_search_result(&self) -> Option<SearchResult<Round>> {
+        self.last_search_result.clone()
+    }
+}
+
+impl PimcPlayer {
+    pub fn new(budget: Budget, use_inference: bool) -> Self {
+        PimcPlayer {
+            solver: Solver::default(),
+            budget,
+            use_inference,
+            last_search_result: None,
+        }
+    }
+}
+
+fn is_exhausted(budget: Budget, num_samples: usize, started: Instant) -> bool {
+    match budget {
+        Budget::Time(time) => started.elapsed().as_millis() > time,
+        Budget::Simulations(max_samples) => num_samples >= max_samples,
+    }
+}
+
+impl Default for PimcPlayer {
+    fn default() -> Self {
+        Self::new(Budget::Time(500), true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::PimcPlayer;
+    use crate::{
+        inference::Inference,
+        players::{random_player::RandomPlayer, Player},
+        random,
+        round::Round,
+        search::searcher::Budget,
+    };
+
+    #[test]
+    fn test_beats_random() {
+        random::seed(Some(16));
+        let mut pimc = PimcPlayer::new(Budget::Simulations(5), true);
+        let mut random_player = RandomPlayer;
+        let (mut pimc_points, mut num_points) = (0i32, 0i32);
+
+        for _ in 0..100 {
+            // a random position with 4 tricks left
+            let mut start = Round::new(0);
+            while start.played_cards().len() < 16 {
+                let action = start.possible_actions().pop_random().unwrap();
+                start.apply_action(action);
+            }
+
+            // play it out twice, with pimc on either team
+            for pimc_team in 0..2 {
+                let mut round = start;
+                while !round.is_terminal() {
+                    let action = match round.turn() % 2 == pimc_team {
+                        true => pimc.decide(round, &Inference::default()),
+                        false => random_player.decide(round, &Inference::default()),
+                    };
+                    round.apply_action(action);
+                }
+                pimc_points += (round.scores()[pimc_team] - start.scores()[pimc_team]) as i32;
+                num_points += (60 - start.scores()[0] - start.scores()[1]) as i32;
+            }
+        }
+
+        // random against random would be even
+        assert!(pimc_points * 20 > num_points * 11);
+    }
+}
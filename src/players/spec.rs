@@ -0,0 +1,290 @@
+//! a small language to describe players on the command line, a name optionally
+//! followed by options, e.g. `random` or `mcts:time=200,inference=on`
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use super::{
+    heuristic_player::HeuristicPlayer,
+    mcts_player::{MctsPlayer, Rollout, SOLVE_BELOW},
+    pimc_player::PimcPlayer,
+    random_player::RandomPlayer,
+    Player,
+};
+use crate::{
+    params::{Params, TUNABLES},
+    search::searcher::Budget,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerSpec {
+    Random,
+    Heuristic,
+    /// options: `time=<ms>` or `sims=<n>`, `nodes=<n>`, `solve=<cards>`,
+    /// `threads=<n>`, `rollout=random|heuristic`, `inference=on|off`, `params=<file>`
+    /// and every tunable param by its name, e.g. `exploration=1.2`
+    Mcts {
+        budget: Budget,
+        max_nodes: Option<usize>,
+        solve_below: usize,
+        num_threads: usize,
+        rollout: Rollout,
+        params: Params,
+        use_inference: bool,
+    },
+    /// options: `time=<ms>` or `samples=<n>` and `inference=on|off`
+    Pimc {
+        budget: Budget,
+        use_inference: bool,
+    },
+}
+
+impl PlayerSpec {
+    pub fn build(&self) -> Box<dyn Player> {
+        match *self {
+            PlayerSpec::Random => RandomPlayer::boxed(),
+            PlayerSpec::Heuristic => HeuristicPlayer::boxed(),
+            PlayerSpec::Mcts {
+                budget,
+                max_nodes,
+                solve_below,
+                num_threads,
+                rollout,
+                params,
+                use_inference,
+            } => {
+                let mut player = MctsPlayer::with_budget(budget, use_inference);
+                player.set_rollout(rollout);
+                player.set_params(params);
+                player.set_max_nodes(max_nodes);
+                player.set_solve_below(solve_below);
+                player.set_num_threads(num_threads);
+                Box::new(player)
+            }
+            PlayerSpec::Pimc {
+                budget,
+                use_inference,
+            } => Box::new(PimcPlayer::new(budget, use_inference)),
+        }
+    }
+
+    /// the same player with other params, only mcts players have params
+    pub fn with_params(mut self, new_params: Params) -> Self {
+        if let PlayerSpec::Mcts { params, .. } = &mut self {
+            *params = new_params;
+        }
+        self
+    }
+}
+
+impl Default for PlayerSpec {
+    fn default() -> Self {
+        PlayerSpec::Mcts {
+            budget: Budget::Time(500),
+            max_nodes: None,
+            solve_below: SOLVE_BELOW,
+            num_threads: 1,
+            rollout: Rollout::Random,
+            params: Params::default(),
+            use_inference: true,
+        }
+    }
+}
+
+impl Display for PlayerSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerSpec::Random => write!(f, "random"),
+            PlayerSpec::Heuristic => write!(f, "heuristic"),
+            PlayerSpec::Mcts {
+                budget,
+                max_nodes,
+                solve_below,
+                num_threads,
+                rollout,
+                params,
+                use_inference,
+            } => {
+                match budget {
+                    Budget::Time(time) => write!(f, "mcts:time={time}")?,
+                    Budget::Simulations(num_sims) => write!(f, "mcts:sims={num_sims}")?,
+                }
+                if let Some(max_nodes) = max_nodes {
+                    write!(f, ",nodes={max_nodes}")?;
+                }
+                if *solve_below != SOLVE_BELOW {
+                    write!(f, ",solve={solve_below}")?;
+                }
+                if *num_threads != 1 {
+                    write!(f, ",threads={num_threads}")?;
+                }
+                if *rollout != Rollout::Random {
+                    write!(f, ",rollout={rollout}")?;
+                }
+                for (i, tunable) in TUNABLES.iter().enumerate() {
+                    if params.value(i) != Params::default().value(i) {
+                        write!(f, ",{}={}", tunable.name, params.value(i))?;
+                    }
+                }
+                let inference = if *use_inference { "on" } else { "off" };
+                write!(f, ",inference={inference}")
+            }
+            PlayerSpec::Pimc {
+                budget,
+                use_inference,
+            } => {
+                match budget {
+                    Budget::Time(time) => write!(f, "pimc:time={time}")?,
+                    Budget::Simulations(num_samples) => write!(f, "pimc:samples={num_samples}")?,
+                }
+                let inference = if *use_inference { "on" } else { "off" };
+                write!(f, ",inference={inference}")
+            }
+        }
+    }
+}
+
+impl FromStr for PlayerSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, options) = s.split_once(':').unwrap_or((s, ""));
+        let options = options
+            .split(',')
+            .filter(|option| !option.is_empty())
+            .map(|option| {
+                option
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected key=value, got '{option}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match name {
+            "random" | "heuristic" => match options.first() {
+                Some((key, _)) => Err(format!("unknown option '{key}' for {name}")),
+                None if name == "random" => Ok(PlayerSpec::Random),
+                None => Ok(PlayerSpec::Heuristic),
+            },
+            "mcts" | "pimc" => {
+                let mut budget = Budget::Time(500);
+                let mut max_nodes = None;
+                let mut solve_below = SOLVE_BELOW;
+                let mut num_threads = 1;
+                let mut rollout = Rollout::Random;
+                let mut params = Params::default();
+                let mut use_inference = true;
+                // pimc counts samples instead of simulations
+                let count_key = if name == "mcts" { "sims" } else { "samples" };
+
+                for (key, value) in options {
+                    let invalid = || format!("invalid value '{value}' for {key}");
+                    match key {
+                        "time" => budget = Budget::Time(value.parse().map_err(|_| invalid())?),
+                        _ if key == count_key => {
+                            budget = Budget::Simulations(value.parse().map_err(|_| invalid())?)
+                        }
+                        "nodes" if name == "mcts" => {
+                            max_nodes = Some(value.parse().map_err(|_| invalid())?)
+                        }
+                        "solve" if name == "mcts" => {
+                            solve_below = value.parse().map_err(|_| invalid())?
+                        }
+                        "threads" if name == "mcts" => {
+                            num_threads = match value.parse() {
+                                Ok(0) | Err(_) => return Err(invalid()),
+                                Ok(num_threads) => num_threads,
+                            }
+                        }
+                        "rollout" if name == "mcts" => rollout = value.parse()?,
+                        "params" if name == "mcts" => params = Params::load(Path::new(value))?,
+                        _ if name == "mcts" && TUNABLES.iter().any(|t| t.name == key) => {
+                            params.set(key, value)?
+                        }
+                        "inference" => {
+                            use_inference = match value {
+                                "on" => true,
+                                "off" => false,
+                                _ => return Err(invalid()),
+                            }
+                        }
+                        _ => return Err(format!("unknown option '{key}' for {name}")),
+                    }
+                }
+
+                if name == "mcts" {
+                    Ok(PlayerSpec::Mcts {
+                        budget,
+                        max_nodes,
+                        solve_below,
+                        num_threads,
+                        rollout,
+                        params,
+                        use_inference,
+                    })
+                } else {
+                    Ok(PlayerSpec::Pimc {
+                        budget,
+                        use_inference,
+                    })
+                }
+            }
+            _ => Err(format!(
+                "unknown player '{name}', expected random, heuristic, mcts or pimc"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayerSpec, Rollout};
+    use crate::{params::Params, search::searcher::Budget};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("random".parse(), Ok(PlayerSpec::Random));
+        assert_eq!("mcts".parse(), Ok(PlayerSpec::default()));
+        assert_eq!(
+            "mcts:sims=5000,nodes=20000,inference=off".parse(),
+            Ok(PlayerSpec::Mcts {
+                budget: Budget::Simulations(5000),
+                max_nodes: Some(20000),
+                solve_below: 4,
+                num_threads: 1,
+                rollout: Rollout::Random,
+                params: Params::default(),
+                use_inference: false,
+            })
+        );
+
+        for spec in [
+            "random",
+            "heuristic",
+            "mcts:time=200,inference=on",
+            "mcts:sims=10,nodes=5,inference=off",
+            "mcts:time=100,solve=0,inference=on",
+            "mcts:time=100,threads=4,inference=on",
+            "mcts:sims=50,rollout=heuristic,inference=on",
+            "mcts:sims=50,exploration=0.9,low_card=0.55,inference=on",
+            "pimc:samples=20,inference=on",
+        ] {
+            assert_eq!(spec.parse::<PlayerSpec>().unwrap().to_string(), spec);
+        }
+
+        for spec in [
+            "alphazero",
+            "random:time=3",
+            "mcts:time=fast",
+            "mcts:depth=3",
+            "mcts:time",
+            "mcts:threads=0",
+            "mcts:rollout=smart",
+            "mcts:exploration=fast",
+            "mcts:low_card=3",
+            "mcts:params=/nonexistent/params.txt",
+            "pimc:exploration=1",
+            "pimc:nodes=5",
+            "pimc:solve=3",
+        ] {
+            assert!(spec.parse::<PlayerSpec>().is_err());
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! All randomness, i.e. dealing, determinization, random players and the search,
+//! goes through the thread local generator of `romu`. Seeding it, and deriving the
+//! seeds of new threads from it, makes entire runs reproducible.
+//! Only games with duplicate deals use a separate [`DealRng`].
+
+/// seed the generator of the current thread, or use entropy if there's no seed
+pub fn seed(seed: Option<u64>) {
+    match seed {
+        Some(seed) => romu::seed_with_64bit(seed),
+        None => romu::seed(),
+    }
+}
+
+/// a seed for a thread spawned by the current thread, call this
+/// before spawning to keep the spawned thread reproducible
+pub fn thread_seed() -> u64 {
+    romu::u64()
+}
+
+/// the generator used to deal the cards of a game. A seeded one deals the same
+/// cards no matter how the players decided in between, so the same deals can be
+/// played twice with the teams swapped
+#[derive(Default)]
+pub struct DealRng(Option<romu::Rng>);
+
+impl DealRng {
+    pub const fn seeded(seed: u64) -> Self {
+        DealRng(Some(romu::Rng::from_seed_with_64bit(seed)))
+    }
+
+    pub fn mod_usize(&self, n: usize) -> usize {
+        match &self.0 {
+            Some(rng) => rng.mod_usize(n),
+            None => romu::mod_usize(n),
+        }
+    }
+}
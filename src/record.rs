@@ -0,0 +1,413 @@
+//! A text format for entire games, similar to PGN for chess:
+//!
+//! ```text
+//! [Rules "flemish"]
+//! [Scores "61 23"]
+//!
+//! [Round "1"]
+//! [Dealer "2"]
+//! [Hand0 "♠7 ♠J ♣8 ..."]
+//! [Hand1 "..."]
+//! [Hand2 "..."]
+//! [Hand3 "..."]
+//! [Trump "♥"]
+//! [Result "34 26"]
+//! PassTrump ♥ Pass
+//! 1. ♠7 ♠8 ♠K ♠10 {2}
+//! 2. ...
+//! ```
+//!
+//! Every round lists the actions before the first trick on a single line,
+//! followed by one line per trick with the winner of that trick between braces.
+//!
+//! Rules that aren't a preset are written as `[Rules "custom"]`, followed by
+//! a tag for every rule:
+//!
+//! ```text
+//! [Rules "custom"]
+//! [TargetScore "71"]
+//! [MustBuy "true"]
+//! [PartnerMustBuy "false"]
+//! [AllowPassTrump "true"]
+//! [AllowDoubling "true"]
+//! [Multipliers "1 1 2 1 2"]
+//! ```
+//!
+//! The multipliers are those of spades, clubs, hearts, diamonds and no trump.
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use crate::{action::Action, card::Card, rules::Rules, scoring::Scoring, stack::Stack, suit::Suit};
+
+#[derive(Default, Clone, Debug)]
+pub struct RoundRecord {
+    pub dealer: usize,
+    pub hands: [Stack; 4],
+    /// None means the round ended before trump was picked
+    pub trump: Option<Option<Suit>>,
+    pub actions: Vec<Action>,
+    pub trick_winners: Vec<usize>,
+    pub card_scores: [i16; 2],
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct GameRecord {
+    pub rules: Rules,
+    pub rounds: Vec<RoundRecord>,
+    pub scores: [i16; 2],
+}
+
+impl GameRecord {
+    pub fn new(rules: Rules) -> Self {
+        GameRecord {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    pub fn start_round(&mut self, dealer: usize, hands: [Stack; 4]) {
+        self.rounds.push(RoundRecord {
+            dealer,
+            hands,
+            ..Default::default()
+        });
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?
+            .parse()
+    }
+
+    /// the round that is currently being recorded
+    pub fn current_round(&mut self) -> &mut RoundRecord {
+        self.rounds.last_mut().unwrap()
+    }
+}
+
+fn write_action(f: &mut std::fmt::Formatter<'_>, action: Action) -> std::fmt::Result {
+    match action {
+        Action::PickTrump(Some(suit)) => write!(f, "{suit}"),
+        Action::PickTrump(None) => write!(f, "NoTrump"),
+        Action::PassTrump => write!(f, "PassTrump"),
+        Action::Double => write!(f, "Double"),
+        Action::Redouble => write!(f, "Redouble"),
+        Action::Pass => write!(f, "Pass"),
+        Action::PlayCard(card) => write!(f, "{card}"),
+    }
+}
+
+fn parse_action(token: &str) -> Result<Action, String> {
+    match token {
+        "NoTrump" => Ok(Action::PickTrump(None)),
+        "PassTrump" => Ok(Action::PassTrump),
+        "Double" => Ok(Action::Double),
+        "Redouble" => Ok(Action::Redouble),
+        "Pass" => Ok(Action::Pass),
+        _ => match token.parse::<Suit>() {
+            Ok(suit) => Ok(Action::PickTrump(Some(suit))),
+            Err(_) => token.parse::<Card>().map(Action::PlayCard),
+        },
+    }
+}
+
+fn write_tag(f: &mut std::fmt::Formatter<'_>, name: &str, value: impl Display) -> std::fmt::Result {
+    writeln!(f, "[{name} \"{value}\"]")
+}
+
+fn parse_tag(line: &str) -> Result<(&str, &str), String> {
+    let invalid = || format!("invalid tag '{line}'");
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = inner.split_once(' ').ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    Ok((name, value))
+}
+
+fn parse_numbers<T: FromStr, const N: usize>(value: &str) -> Result<[T; N], String> {
+    let values = value
+        .split_whitespace()
+        .map(|v| v.parse::<T>().map_err(|_| format!("invalid number '{v}'")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    values
+        .try_into()
+        .map_err(|_| format!("expected {N} numbers, got '{value}'"))
+}
+
+fn parse_pair<T: FromStr>(value: &str) -> Result<[T; 2], String> {
+    parse_numbers(value)
+}
+
+fn write_rules(f: &mut std::fmt::Formatter<'_>, rules: &Rules) -> std::fmt::Result {
+    if let Some(name) = rules.preset_name() {
+        return write_tag(f, "Rules", name);
+    }
+
+    write_tag(f, "Rules", "custom")?;
+    write_tag(f, "TargetScore", rules.target_score)?;
+    write_tag(f, "MustBuy", rules.must_buy)?;
+    write_tag(f, "PartnerMustBuy", rules.partner_must_buy)?;
+    write_tag(f, "AllowPassTrump", rules.allow_pass_trump)?;
+    write_tag(f, "AllowDoubling", rules.allow_doubling)?;
+    let Scoring {
+        trump_multipliers: [spades, clubs, hearts, diamonds],
+        no_trump_multiplier,
+    } = rules.scoring;
+    write_tag(
+        f,
+        "Multipliers",
+        format!("{spades} {clubs} {hearts} {diamonds} {no_trump_multiplier}"),
+    )
+}
+
+/// sets the rule of tag `name`, returns false when it isn't a rule
+fn parse_rule(rules: &mut Rules, name: &str, value: &str) -> Result<bool, String> {
+    let parse_bool = || {
+        value
+            .parse::<bool>()
+            .map_err(|_| format!("invalid {name} '{value}'"))
+    };
+
+    match name {
+        "Rules" if value == "custom" => *rules = Rules::default(),
+        "Rules" => {
+            *rules = Rules::preset(value).ok_or_else(|| format!("unknown rules '{value}'"))?
+        }
+        "TargetScore" => {
+            rules.target_score = value
+                .parse()
+                .map_err(|_| format!("invalid target score '{value}'"))?
+        }
+        "MustBuy" => rules.must_buy = parse_bool()?,
+        "PartnerMustBuy" => rules.partner_must_buy = parse_bool()?,
+        "AllowPassTrump" => rules.allow_pass_trump = parse_bool()?,
+        "AllowDoubling" => rules.allow_doubling = parse_bool()?,
+        "Multipliers" => {
+            let [spades, clubs, hearts, diamonds, no_trump] = parse_numbers(value)?;
+            rules.scoring = Scoring {
+                trump_multipliers: [spades, clubs, hearts, diamonds],
+                no_trump_multiplier: no_trump,
+            };
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+impl Display for RoundRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_tag(f, "Dealer", self.dealer)?;
+        for (i, hand) in self.hands.iter().enumerate() {
+            let cards = hand.into_iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            write_tag(f, &format!("Hand{i}"), cards.join(" "))?;
+        }
+        match self.trump {
+            Some(Some(suit)) => write_tag(f, "Trump", suit)?,
+            Some(None) => write_tag(f, "Trump", "NoTrump")?,
+            None => {}
+        }
+        write_tag(
+            f,
+            "Result",
+            format!("{} {}", self.card_scores[0], self.card_scores[1]),
+        )?;
+
+        let num_bids = self
+            .actions
+            .iter()
+            .take_while(|a| !matches!(a, Action::PlayCard(_)))
+            .count();
+        for (i, &action) in self.actions[..num_bids].iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write_action(f, action)?;
+        }
+        writeln!(f)?;
+
+        for (i, trick) in self.actions[num_bids..].chunks(4).enumerate() {
+            write!(f, "{}.", i + 1)?;
+            for &action in trick {
+                write!(f, " ")?;
+                write_action(f, action)?;
+            }
+            if let Some(winner) = self.trick_winners.get(i) {
+                write!(f, " {{{winner}}}")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_rules(f, &self.rules)?;
+        write_tag(
+            f,
+            "Scores",
+            format!("{} {}", self.scores[0], self.scores[1]),
+        )?;
+
+        for (i, round) in self.rounds.iter().enumerate() {
+            writeln!(f)?;
+            write_tag(f, "Round", i + 1)?;
+            write!(f, "{round}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::default();
+
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if line.starts_with('[') {
+                let (name, value) = parse_tag(line)?;
+                if record.rounds.is_empty() && parse_rule(&mut record.rules, name, value)? {
+                    continue;
+                }
+
+                match name {
+                    "Scores" => record.scores = parse_pair(value)?,
+                    "Round" => record.rounds.push(RoundRecord::default()),
+                    _ => {
+                        let round = record
+                            .rounds
+                            .last_mut()
+                            .ok_or_else(|| format!("tag '{name}' outside of a round"))?;
+
+                        match name {
+                            "Dealer" => {
+                                round.dealer = value
+                                    .parse()
+                                    .map_err(|_| format!("invalid dealer '{value}'"))?
+                            }
+                            "Trump" => {
+                                round.trump = match parse_action(value)? {
+                                    Action::PickTrump(trump) => Some(trump),
+                                    _ => return Err(format!("invalid trump '{value}'")),
+                                }
+                            }
+                            "Result" => round.card_scores = parse_pair(value)?,
+                            _ => match name.strip_prefix("Hand").map(str::parse::<usize>) {
+                                Some(Ok(i)) if i < 4 => {
+                                    for token in value.split_whitespace() {
+                                        round.hands[i].push(token.parse::<Card>()?);
+                                    }
+                                }
+                                _ => return Err(format!("unknown tag '{name}'")),
+                            },
+                        }
+                    }
+                }
+            } else {
+                let round = record
+                    .rounds
+                    .last_mut()
+                    .ok_or_else(|| format!("actions '{line}' outside of a round"))?;
+
+                for token in line.split_whitespace() {
+                    if token.ends_with('.') {
+                        // trick number
+                        continue;
+                    }
+
+                    if let Some(winner) = token.strip_prefix('{').and_then(|t| t.strip_suffix('}'))
+                    {
+                        let winner = winner
+                            .parse()
+                            .map_err(|_| format!("invalid trick winner '{token}'"))?;
+                        round.trick_winners.push(winner);
+                    } else {
+                        round.actions.push(parse_action(token)?);
+                    }
+                }
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::GameRecord;
+    use crate::{
+        game::{tests::random_players, Game},
+        round::Round,
+        rules::Rules,
+        scoring::Scoring,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let mut players = random_players();
+
+        let mut game = Game::new(Rules::walloon());
+        game.enable_recording();
+        game.play(&mut players);
+
+        let record = game.record().unwrap();
+        let text = record.to_string();
+        let parsed = text.parse::<GameRecord>().unwrap();
+
+        assert!(parsed.to_string() == text);
+        assert!(parsed.rules == Rules::walloon());
+        assert!(parsed.rounds.len() == game.num_rounds());
+
+        for round in &parsed.rounds {
+            let mut state = Round::from_deal(round.dealer, round.hands, parsed.rules);
+            for &action in &round.actions {
+                assert!(state.possible_actions().has(&action));
+                state.apply_action(action);
+            }
+
+            assert!(state.is_terminal());
+            assert!(state.scores() == round.card_scores);
+        }
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let rules = Rules {
+            target_score: 71,
+            allow_pass_trump: false,
+            scoring: Scoring {
+                trump_multipliers: [1, 1, 2, 1],
+                no_trump_multiplier: 3,
+            },
+            ..Rules::flemish()
+        };
+        assert!(rules.preset_name().is_none());
+
+        let text = GameRecord::new(rules).to_string();
+        let parsed = text.parse::<GameRecord>().unwrap();
+        assert!(parsed.rules == rules);
+        assert!(parsed.to_string() == text);
+
+        assert!("[Rules \"dutch\"]".parse::<GameRecord>().is_err());
+        assert!("[Rules \"custom\"]\n[MustBuy \"maybe\"]"
+            .parse::<GameRecord>()
+            .is_err());
+    }
+}
@@ -1,17 +1,30 @@
 use std::fmt::Debug;
 
 use ismcts::state::State;
-use rand::seq::IndexedRandom;
 
 use crate::{
-    action::Action, action_collection::ActionCollection, card::Card, inference::Inference,
-    stack::Stack, suit::Suit, trick::Trick,
+    action::Action,
+    action_collection::{ActionCollection, DOUBLE_MASK, PASS_MASK, PASS_TRUMP_MASK, REDOUBLE_MASK},
+    card::Card,
+    inference::Inference,
+    random::DealRng,
+    rules::Rules,
+    sampler::sample_deal,
+    stack::Stack,
+    suit::Suit,
+    trick::Trick,
 };
 
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RoundPhase {
     #[default]
     PickTrump,
+    /// the dealer passed, so now their partner has to pick trump
+    PartnerPickTrump,
+    /// the opponents of the trump picking team can double the stakes
+    Double,
+    /// the trump picking team can double the stakes once more
+    Redouble,
     PlayCards,
 }
 
@@ -19,11 +32,15 @@ pub enum RoundPhase {
 pub struct Round {
     turn: usize,
     dealer: usize,
+    trump_chooser: usize,
+    /// the stakes are multiplied by 2 for every doubling
+    doublings: u8,
     player_cards: [Stack; 4],
     played_cards: Stack,
     scores: [i16; 2],
     trick: Trick,
     phase: RoundPhase,
+    rules: Rules,
 }
 
 impl Round {
@@ -34,6 +51,8 @@ impl Round {
         played_cards: Stack,
         player_card_counts: [usize; 4],
         dealer: usize,
+        trump_chooser: usize,
+        doublings: u8,
         turn: usize,
         phase: RoundPhase,
         trump: Option<Suit>,
@@ -50,6 +69,8 @@ impl Round {
 
         round.turn = turn;
         round.dealer = dealer;
+        round.trump_chooser = trump_chooser;
+        round.doublings = doublings;
         round.phase = phase;
         round.scores = scores;
         round.trick.set_trump(trump);
@@ -57,82 +78,128 @@ impl Round {
         round
     }
 
-    pub fn observe_action(&self, observer: usize, action: Action, inference: &Inference) -> Self {
-        let mut round = *self;
-        if let Action::PlayCard(card) = action {
-            if !round.player_cards(round.turn()).has_card(card) {
-                round.player_cards[round.turn()].pop_random_card();
-            }
-        }
-
-        round.apply_action(action);
-        round.randomize(observer, inference)
-    }
-
     fn randomize_for(
         &self,
         observer: usize,
         observer_cards: Stack,
         played_cards: Stack,
-        mut player_card_counts: [usize; 4],
+        player_card_counts: [usize; 4],
         inference: &Inference,
     ) -> Self {
         let mut round = *self;
-        let mut cards_to_deal = Stack::ALL ^ observer_cards ^ played_cards;
-        let mut players = Vec::with_capacity(4);
+        let cards_to_deal = Stack::ALL ^ observer_cards ^ played_cards;
+        let others = [1, 2, 3].map(|i| (observer + i) % 4);
+        let counts = others.map(|player| player_card_counts[player]);
+        let can_have = |i: usize, card| inference.can_have(others[i], card);
+
+        let hands = sample_deal(cards_to_deal, counts, |i, card| {
+            can_have(i, card) as u8 as f64 * inference.weight(others[i], card) as f64
+        })
+        // the weights can rule out every deal when they got things wrong
+        .or_else(|| {
+            sample_deal(cards_to_deal, counts, |i, card| {
+                can_have(i, card) as u8 as f64
+            })
+        })
+        // and the constraints when they don't belong to this round
+        .or_else(|| sample_deal(cards_to_deal, counts, |_, _| 1.))
+        .unwrap();
+
+        for (player, hand) in others.into_iter().zip(hands) {
+            round.player_cards[player] = hand;
+        }
+        round.player_cards[observer] = observer_cards;
+
+        round
+    }
 
+    /// every deal of the hidden cards that `observer` considers possible, with
+    /// its likelihood under `inference`. Only feasible near the end of a round
+    pub fn determinizations(&self, observer: usize, inference: &Inference) -> Vec<(Self, f64)> {
+        let mut round = *self;
+        let hidden = self.unplayed_cards() & !self.player_cards[observer];
         for i in 0..4 {
-            round.player_cards[i].clear();
+            if i != observer {
+                round.player_cards[i].clear();
+            }
         }
 
-        let mut rng = rand::rng();
-        while let Some(card) = cards_to_deal.pop_lowest() {
-            for i in 1..=3 {
-                let player = (observer + i) % 4;
-                if player_card_counts[player] > 0 {
-                    players.push(player);
-                }
-            }
-            let chosen_player = players
-                .choose_weighted(&mut rng, |&player| inference.weight(player, card))
-                .unwrap_or(players.choose(&mut rng).unwrap());
+        let mut deals = vec![];
+        round.deal_hidden(observer, hidden, 1., self, inference, &mut deals);
+        deals
+    }
 
-            round.player_cards[*chosen_player].push(card);
-            player_card_counts[*chosen_player] -= 1;
-            players.clear();
+    fn deal_hidden(
+        &mut self,
+        observer: usize,
+        mut hidden: Stack,
+        likelihood: f64,
+        original: &Self,
+        inference: &Inference,
+        deals: &mut Vec<(Self, f64)>,
+    ) {
+        let Some(card) = hidden.pop_lowest() else {
+            deals.push((*self, likelihood));
+            return;
+        };
+
+        for i in 1..=3 {
+            let player = (observer + i) % 4;
+            let weight = inference.weight(player, card);
+            if inference.can_have(player, card)
+                && self.player_cards[player].len() < original.player_cards[player].len()
+            {
+                self.player_cards[player].push(card);
+                let likelihood = likelihood * weight as f64;
+                self.deal_hidden(observer, hidden, likelihood, original, inference, deals);
+                self.player_cards[player].remove(card);
+            }
         }
+    }
 
-        round.player_cards[observer] = observer_cards;
+    pub fn new(dealer: usize) -> Self {
+        Self::with_deal_rng(dealer, &DealRng::default())
+    }
+
+    pub fn with_deal_rng(dealer: usize, deal_rng: &DealRng) -> Self {
+        let mut round = Round::default();
+
+        round.set_dealer(dealer);
+        round.deal_cards(deal_rng);
 
         round
     }
 
-    pub fn new(dealer: usize) -> Self {
+    /// start a round from a known deal, e.g. to replay a recorded game
+    pub fn from_deal(dealer: usize, hands: [Stack; 4], rules: Rules) -> Self {
         let mut round = Round::default();
 
         round.set_dealer(dealer);
-        round.deal_cards();
+        round.set_rules(rules);
+        round.player_cards = hands;
 
         round
     }
 
     const fn set_dealer(&mut self, dealer: usize) {
         self.dealer = dealer;
+        self.trump_chooser = dealer;
         self.turn = (dealer + 1) % 4;
     }
 
-    pub fn setup_for_next_round(&mut self) {
+    pub fn setup_for_next_round(&mut self, deal_rng: &DealRng) {
         let next_dealer = (self.dealer + 1) % 4;
         self.set_dealer(next_dealer);
-        self.deal_cards();
+        self.deal_cards(deal_rng);
 
         self.played_cards = Stack::default();
         self.scores = [0; 2];
         self.trick.clear();
+        self.doublings = 0;
         self.phase = RoundPhase::PickTrump;
     }
 
-    fn deal_cards(&mut self) {
+    fn deal_cards(&mut self, deal_rng: &DealRng) {
         let mut indices: [u32; 32] = std::array::from_fn(|i| i as u32);
         let mut cards = [Stack::default(); 3];
 
@@ -140,7 +207,7 @@ impl Round {
         let n = indices.len() / 4;
 
         for i in (n..4 * n).rev() {
-            let j = romu::mod_usize(i + 1);
+            let j = deal_rng.mod_usize(i + 1);
             indices.swap(i, j);
 
             cards[(i / n) - 1] |= 1 << indices[i];
@@ -166,7 +233,40 @@ impl Round {
 
     const fn set_trump(&mut self, trump: Option<Suit>) {
         self.trick.set_trump(trump);
+        if self.rules.allow_doubling {
+            self.phase = RoundPhase::Double;
+            self.turn = (self.dealer + 1) % 4;
+        } else {
+            self.start_playing();
+        }
+    }
+
+    const fn pass_trump(&mut self) {
+        self.trump_chooser = (self.dealer + 2) % 4;
+        self.phase = RoundPhase::PartnerPickTrump;
+    }
+
+    const fn double(&mut self) {
+        self.doublings += 1;
+        match self.phase {
+            RoundPhase::Double => self.phase = RoundPhase::Redouble,
+            _ => self.start_playing(),
+        }
+    }
+
+    const fn pass(&mut self) {
+        // when the opponent to the left of the dealer passes, the other one can still double
+        if matches!(self.phase, RoundPhase::Double) && self.turn == (self.dealer + 1) % 4 {
+            self.turn = (self.dealer + 3) % 4;
+        } else {
+            self.start_playing();
+        }
+    }
+
+    /// the player to the left of the dealer leads the first trick
+    const fn start_playing(&mut self) {
         self.phase = RoundPhase::PlayCards;
+        self.turn = (self.dealer + 1) % 4;
     }
 
     const fn on_trick_finish(&mut self) {
@@ -192,8 +292,10 @@ impl Round {
         // this also means we're not the first player, i.e. the suit
         // to follow has been determined
         if let Some((winning_card, winning_player)) = self.trick.winner() {
-            // our team isn't winning
-            if winning_player % 2 != self.turn % 2 {
+            // our team isn't winning, or we have to buy regardless
+            if self.rules.must_buy
+                && (winning_player % 2 != self.turn % 2 || self.rules.partner_must_buy)
+            {
                 // have to buy if possible, but can't 'under-buy', except if that's our only possible move
                 if let Some(trump) = self.trick.trump() {
                     let mut mask = Stack::all_above(winning_card) & winning_card.suit().mask();
@@ -225,9 +327,14 @@ impl Round {
     }
 
     fn possible_trump_actions(&self) -> <Self as State>::ActionList {
-        let cards = self.player_cards[self.dealer];
+        let cards = self.player_cards[self.trump_chooser()];
         let mut bits = 1 << 4;
 
+        // only the dealer can pass, their partner has to choose
+        if self.rules.allow_pass_trump && self.phase == RoundPhase::PickTrump {
+            bits |= PASS_TRUMP_MASK;
+        }
+
         for suit in [Suit::Spades, Suit::Clubs, Suit::Hearts, Suit::Diamonds] {
             if cards.has_suit(suit) {
                 bits |= 1 << suit as u8;
@@ -237,6 +344,13 @@ impl Round {
         ActionCollection::Trumps(bits)
     }
 
+    fn possible_bet_actions(&self) -> <Self as State>::ActionList {
+        match self.phase {
+            RoundPhase::Double => ActionCollection::Bets(PASS_MASK | DOUBLE_MASK),
+            _ => ActionCollection::Bets(PASS_MASK | REDOUBLE_MASK),
+        }
+    }
+
     pub const fn player_cards(&self, player: usize) -> Stack {
         self.player_cards[player]
     }
@@ -253,6 +367,43 @@ impl Round {
         self.dealer
     }
 
+    /// the player that picks trump this round, i.e. the dealer or,
+    /// if the dealer passed, their partner
+    pub const fn trump_chooser(&self) -> usize {
+        self.trump_chooser
+    }
+
+    /// multiplier for the points of this round due to (re)doubling
+    pub const fn stake(&self) -> i16 {
+        1 << self.doublings
+    }
+
+    /// multiplier for the points of this round, due to both
+    /// (re)doubling and the choice of trump
+    pub const fn multiplier(&self) -> i16 {
+        self.stake() * self.rules.scoring.multiplier(self.trump())
+    }
+
+    pub const fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    pub const fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// the winning team and the number of points they earn
+    pub fn outcome(&self) -> (usize, i16) {
+        let winning_team = if self.scores[0] > self.scores[1] {
+            0
+        } else {
+            1
+        };
+        let points = (self.scores[winning_team] - 30) * self.multiplier();
+
+        (winning_team, points)
+    }
+
     pub const fn phase(&self) -> RoundPhase {
         self.phase
     }
@@ -272,6 +423,18 @@ impl Round {
     pub const fn scores(&self) -> [i16; 2] {
         self.scores
     }
+
+    /// the reward of a team ending the round with `card_score`, from -1 to 1.
+    /// The stakes are included, relative to the highest the rules allow,
+    /// so the search can weigh a double against the round it's played in
+    pub fn reward_of(&self, card_score: f32) -> f32 {
+        (card_score - 30.) * self.multiplier() as f32 / (30 * self.rules.max_multiplier()) as f32
+    }
+
+    /// the card score that [`Round::reward_of`] turns into `reward`
+    pub fn card_score_of(&self, reward: f32) -> f32 {
+        30. + reward * (30 * self.rules.max_multiplier()) as f32 / self.multiplier() as f32
+    }
 }
 
 impl State for Round {
@@ -281,7 +444,10 @@ impl State for Round {
 
     fn turn(&self) -> usize {
         match self.phase {
-            RoundPhase::PickTrump => self.dealer,
+            RoundPhase::PickTrump | RoundPhase::PartnerPickTrump => self.trump_chooser(),
+            // the opponents double in turn, starting left of the dealer
+            RoundPhase::Double => self.turn,
+            RoundPhase::Redouble => self.trump_chooser(),
             RoundPhase::PlayCards => self.turn,
         }
     }
@@ -301,7 +467,8 @@ impl State for Round {
 
     fn possible_actions(&self) -> Self::ActionList {
         match self.phase {
-            RoundPhase::PickTrump => self.possible_trump_actions(),
+            RoundPhase::PickTrump | RoundPhase::PartnerPickTrump => self.possible_trump_actions(),
+            RoundPhase::Double | RoundPhase::Redouble => self.possible_bet_actions(),
             RoundPhase::PlayCards => self.possible_card_actions(),
         }
     }
@@ -310,6 +477,9 @@ impl State for Round {
         match action {
             Action::PlayCard(card) => self.play_card(card),
             Action::PickTrump(trump) => self.set_trump(trump),
+            Action::PassTrump => self.pass_trump(),
+            Action::Double | Action::Redouble => self.double(),
+            Action::Pass => self.pass(),
         }
     }
 
@@ -321,7 +491,7 @@ impl State for Round {
         assert!(self.is_terminal());
 
         let team = perspective % 2;
-        (self.scores[team] - 30) as f32 / 30.
+        self.reward_of(self.scores[team] as f32)
     }
 }
 
@@ -334,6 +504,8 @@ impl Debug for Round {
         f.debug_struct("Round")
             .field("turn", &self.turn)
             .field("dealer", &self.dealer)
+            .field("trump_chooser", &self.trump_chooser)
+            .field("multiplier", &self.multiplier())
             .field("played_cards", &self.played_cards)
             .field("trick", &self.trick)
             .field("scores", &self.scores)
@@ -341,3 +513,123 @@ impl Debug for Round {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::{Round, RoundPhase};
+    use crate::{action::Action, rules::Rules, scoring::Scoring, suit::Suit};
+
+    #[test]
+    fn test_pass_trump() {
+        let mut round = Round::new(1);
+        assert!(round.turn() == 1);
+        assert!(round.possible_actions().has(&Action::PassTrump));
+
+        round.apply_action(Action::PassTrump);
+        assert!(round.phase() == RoundPhase::PartnerPickTrump);
+        assert!(round.turn() == 3);
+        assert!(!round.possible_actions().has(&Action::PassTrump));
+
+        round.apply_action(Action::PickTrump(None));
+        assert!(round.phase() == RoundPhase::Double);
+        assert!(round.turn() == 2);
+    }
+
+    #[test]
+    fn test_doubling() {
+        let mut round = Round::new(1);
+        round.apply_action(Action::PassTrump);
+        round.apply_action(Action::PickTrump(None));
+
+        assert!(round.possible_actions().has(&Action::Double));
+        round.apply_action(Action::Double);
+        assert!(round.phase() == RoundPhase::Redouble);
+        // the partner picked trump, so they're the one to redouble
+        assert!(round.turn() == 3);
+
+        round.apply_action(Action::Redouble);
+        assert!(round.phase() == RoundPhase::PlayCards);
+        assert!(round.turn() == 2);
+        assert!(round.stake() == 4);
+    }
+
+    #[test]
+    fn test_second_opponent_doubles() {
+        let mut round = Round::new(1);
+        round.apply_action(Action::PickTrump(None));
+        assert!(round.turn() == 2);
+
+        round.apply_action(Action::Pass);
+        assert!(round.phase() == RoundPhase::Double);
+        assert!(round.turn() == 0);
+
+        round.apply_action(Action::Double);
+        assert!(round.phase() == RoundPhase::Redouble);
+        assert!(round.turn() == 1);
+
+        round.apply_action(Action::Pass);
+        assert!(round.phase() == RoundPhase::PlayCards);
+        assert!(round.turn() == 2);
+        assert!(round.stake() == 2);
+    }
+
+    #[test]
+    fn test_both_opponents_pass() {
+        let mut round = Round::new(1);
+        round.apply_action(Action::PickTrump(None));
+        round.apply_action(Action::Pass);
+        round.apply_action(Action::Pass);
+
+        assert!(round.phase() == RoundPhase::PlayCards);
+        assert!(round.turn() == 2);
+        assert!(round.stake() == 1);
+    }
+
+    #[test]
+    fn test_multiplier() {
+        let mut round = Round::new(1);
+        round.set_rules(Rules {
+            scoring: Scoring {
+                trump_multipliers: [1, 1, 3, 1],
+                no_trump_multiplier: 2,
+            },
+            ..Rules::default()
+        });
+
+        let mut no_trump = round;
+        no_trump.apply_action(Action::PickTrump(None));
+        assert!(no_trump.multiplier() == 2);
+        no_trump.apply_action(Action::Double);
+        assert!(no_trump.multiplier() == 4);
+
+        let mut hearts = round;
+        hearts.apply_action(Action::PickTrump(Some(Suit::Hearts)));
+        assert!(hearts.multiplier() == 3);
+
+        let mut spades = round;
+        spades.apply_action(Action::PickTrump(Some(Suit::Spades)));
+        assert!(spades.multiplier() == 1);
+
+        round.set_rules(Rules {
+            scoring: Scoring::flat(),
+            ..Rules::default()
+        });
+        round.apply_action(Action::PickTrump(None));
+        assert!(round.multiplier() == 1);
+    }
+
+    #[test]
+    fn test_reward_range() {
+        let mut round = Round::new(1);
+        round.apply_action(Action::PickTrump(None));
+        round.apply_action(Action::Double);
+        round.apply_action(Action::Redouble);
+
+        // the highest stakes the rules allow
+        assert!(round.reward_of(60.) == 1.);
+        assert!(round.reward_of(0.) == -1.);
+        assert!(round.card_score_of(round.reward_of(42.)) == 42.);
+    }
+}
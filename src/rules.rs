@@ -0,0 +1,84 @@
+use crate::scoring::Scoring;
+
+/// the house rules a game is played with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// the first team to reach this score wins the game
+    pub target_score: i16,
+    /// a player that can't follow or loses the trick has to buy, i.e. beat the winning card,
+    /// if possible. When playing with trump, this means they have to (over)trump
+    pub must_buy: bool,
+    /// players also have to buy when their partner is winning the trick
+    pub partner_must_buy: bool,
+    /// the dealer can let their partner pick trump
+    pub allow_pass_trump: bool,
+    /// the opponents can double after trump is picked, which the trump picking team can redouble
+    pub allow_doubling: bool,
+    pub scoring: Scoring,
+}
+
+impl Rules {
+    pub const PRESETS: [(&'static str, Rules); 3] = [
+        ("flemish", Rules::flemish()),
+        ("walloon", Rules::walloon()),
+        ("101", Rules::hundred_one()),
+    ];
+
+    pub const fn flemish() -> Self {
+        Rules {
+            target_score: 61,
+            must_buy: true,
+            partner_must_buy: false,
+            allow_pass_trump: true,
+            allow_doubling: true,
+            scoring: Scoring::double_no_trump(),
+        }
+    }
+
+    /// partners have to overtrump each other, and the dealer always picks trump
+    pub const fn walloon() -> Self {
+        Rules {
+            partner_must_buy: true,
+            allow_pass_trump: false,
+            ..Rules::flemish()
+        }
+    }
+
+    /// a longer game to 101 points, with flat scoring
+    pub const fn hundred_one() -> Self {
+        Rules {
+            target_score: 101,
+            allow_doubling: false,
+            scoring: Scoring::flat(),
+            ..Rules::flemish()
+        }
+    }
+
+    /// the most the points of a round can be multiplied by,
+    /// by redoubling with the trump that counts the most
+    pub fn max_multiplier(&self) -> i16 {
+        let max_stake = if self.allow_doubling { 4 } else { 1 };
+        max_stake * self.scoring.max_multiplier()
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        Self::PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, rules)| *rules)
+    }
+
+    pub fn preset_name(&self) -> Option<&'static str> {
+        Self::PRESETS
+            .iter()
+            .find(|(_, rules)| rules == self)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Default for Rules {
+    /// the flemish rules, so rounds without trump count double
+    fn default() -> Self {
+        Rules::flemish()
+    }
+}
@@ -0,0 +1,231 @@
+//! exact sampling of the hidden cards. Dealing them one at a time, each to a
+//! player chosen by weight, can run into a dead end or skew the odds once
+//! constraints interact. Instead, the total weight of all ways to deal the
+//! remaining cards is counted first, so every card can be dealt with exactly
+//! the probability it has among all deals
+use crate::{array::Array, card::Card, stack::Stack};
+
+/// no hand holds more than this many cards
+const MAX_HAND: usize = 8;
+
+/// deals `cards` to 3 players with `counts` cards each. The probability of a deal
+/// is proportional to the product of `weight(player, card)` over its cards, a weight
+/// of 0 rules a card out for that player. None if no deal has a positive weight
+pub fn sample_deal(
+    cards: Stack,
+    counts: [usize; 3],
+    weight: impl Fn(usize, Card) -> f64,
+) -> Option<[Stack; 3]> {
+    let n = cards.len() as usize;
+    assert_eq!(counts.iter().sum::<usize>(), n);
+    assert!(counts.iter().all(|&count| count <= MAX_HAND));
+    let cards = cards.into_iter().collect::<Array<Card, { 3 * MAX_HAND }>>();
+
+    let mut weights = [[0.; 3]; 3 * MAX_HAND];
+    for (i, &card) in cards.iter().enumerate() {
+        // anything that isn't a positive weight, e.g. NaN, rules the card out
+        weights[i] = std::array::from_fn(|player| {
+            Some(weight(player, card)).filter(|w| *w > 0.).unwrap_or(0.)
+        });
+    }
+
+    // totals[i][a][b] is the total weight of the ways to deal cards i.. when players
+    // 0 and 1 have room for a and b more cards, player 2 takes the rest. Only the
+    // states that can be reached from `counts` are filled in
+    let [count_a, count_b, count_c] = counts;
+    let mut totals = [[[0f64; MAX_HAND + 1]; MAX_HAND + 1]; 3 * MAX_HAND + 1];
+    totals[n][0][0] = 1.;
+    for i in (0..n).rev() {
+        let left = n - i;
+        for a in left.saturating_sub(count_b + count_c)..=left.min(count_a) {
+            for b in (left - a).saturating_sub(count_c)..=(left - a).min(count_b) {
+                let c = left - a - b;
+                let next = &totals[i + 1];
+                let mut total = 0.;
+                if a > 0 {
+                    total += weights[i][0] * next[a - 1][b];
+                }
+                if b > 0 {
+                    total += weights[i][1] * next[a][b - 1];
+                }
+                if c > 0 {
+                    total += weights[i][2] * next[a][b];
+                }
+                totals[i][a][b] = total;
+            }
+        }
+    }
+
+    let [mut a, mut b, _] = counts;
+    if totals[0][a][b] == 0. {
+        return None;
+    }
+
+    let mut hands = [Stack::default(); 3];
+    for (i, &card) in cards.iter().enumerate() {
+        let left = n - i;
+        let next = &totals[i + 1];
+        let options = [
+            (a > 0).then(|| weights[i][0] * next[a - 1][b]),
+            (b > 0).then(|| weights[i][1] * next[a][b - 1]),
+            (left - a - b > 0).then(|| weights[i][2] * next[a][b]),
+        ]
+        .map(|option| option.unwrap_or(0.));
+
+        let player = choose(options);
+        hands[player].push(card);
+        match player {
+            0 => a -= 1,
+            1 => b -= 1,
+            _ => {}
+        }
+    }
+
+    Some(hands)
+}
+
+/// an index with a probability proportional to its weight,
+/// at least one weight should be positive
+fn choose(weights: [f64; 3]) -> usize {
+    let total = weights.iter().sum::<f64>();
+    let mut target = romu::f64() * total;
+    let mut last_candidate = 0;
+
+    for (i, &weight) in weights.iter().enumerate() {
+        if weight > 0. {
+            last_candidate = i;
+            target -= weight;
+            if target < 0. {
+                return i;
+            }
+        }
+    }
+
+    // rounding errors
+    last_candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::sample_deal;
+    use crate::{card::Card, random, stack::Stack, suit::Suit};
+
+    const NUM_SAMPLES: usize = 40_000;
+
+    /// every way to deal `cards` with `counts` cards each, with its weight
+    fn all_deals(
+        cards: &[Card],
+        counts: [usize; 3],
+        weight: &impl Fn(usize, Card) -> f64,
+    ) -> Vec<([Stack; 3], f64)> {
+        let Some((&card, rest)) = cards.split_first() else {
+            return vec![([Stack::default(); 3], 1.)];
+        };
+
+        let mut deals = vec![];
+        for player in 0..3 {
+            let w = weight(player, card);
+            if counts[player] == 0 || w == 0. {
+                continue;
+            }
+
+            let mut counts = counts;
+            counts[player] -= 1;
+            for (mut hands, rest_weight) in all_deals(rest, counts, weight) {
+                hands[player].push(card);
+                deals.push((hands, w * rest_weight));
+            }
+        }
+        deals
+    }
+
+    /// the chi-squared statistic of the sampled deals against their exact probabilities,
+    /// and the degrees of freedom
+    fn chi_squared(
+        cards: Stack,
+        counts: [usize; 3],
+        weight: impl Fn(usize, Card) -> f64,
+    ) -> (f64, f64) {
+        let deals = all_deals(&cards.into_iter().collect::<Vec<_>>(), counts, &weight);
+        let total = deals.iter().map(|(_, w)| w).sum::<f64>();
+
+        let mut observed = HashMap::new();
+        for _ in 0..NUM_SAMPLES {
+            let hands = sample_deal(cards, counts, &weight).unwrap();
+            *observed.entry(hands).or_insert(0) += 1;
+        }
+        // nothing outside the possible deals is sampled
+        assert!(observed
+            .keys()
+            .all(|hands| deals.iter().any(|(deal, _)| deal == hands)));
+
+        let statistic = deals
+            .iter()
+            .map(|(hands, w)| {
+                let expected = NUM_SAMPLES as f64 * w / total;
+                let observed = *observed.get(hands).unwrap_or(&0) as f64;
+                (observed - expected).powi(2) / expected
+            })
+            .sum::<f64>();
+        (statistic, deals.len() as f64 - 1.)
+    }
+
+    /// far beyond what chance allows, about 5 standard deviations
+    fn assert_unbiased((statistic, df): (f64, f64)) {
+        assert!(
+            statistic < df + 5. * (2. * df).sqrt(),
+            "{statistic} with {df} degrees of freedom"
+        );
+    }
+
+    fn stack(cards: &str) -> Stack {
+        let mut stack = Stack::default();
+        for card in cards.split_whitespace() {
+            stack.push(card.parse().unwrap());
+        }
+        stack
+    }
+
+    #[test]
+    fn test_uniform() {
+        random::seed(Some(21));
+        let cards = stack("S7 S8 S9 H7 H8 H9 C7");
+        assert_unbiased(chi_squared(cards, [3, 2, 2], |_, _| 1.));
+    }
+
+    #[test]
+    fn test_voids() {
+        random::seed(Some(22));
+        // player 0 is void in spades and player 1 in hearts, so the spades have
+        // to go to players 1 and 2, which dealing card by card gets wrong
+        let cards = stack("S7 S8 S9 H7 H8 H9 C7");
+        let weight = |player: usize, card: Card| match (player, card.suit()) {
+            (0, Suit::Spades) | (1, Suit::Hearts) => 0.,
+            _ => 1.,
+        };
+        assert_unbiased(chi_squared(cards, [2, 3, 2], weight));
+    }
+
+    #[test]
+    fn test_weighted() {
+        random::seed(Some(23));
+        let cards = stack("S7 S8 S9 S10 H7 H10");
+        // player 2 likely holds the tens
+        let weight = |player: usize, card: Card| match (player, card.value()) {
+            (2, 7) => 4.,
+            (0, 7) => 0.5,
+            _ => 1.,
+        };
+        assert_unbiased(chi_squared(cards, [2, 2, 2], weight));
+    }
+
+    #[test]
+    fn test_impossible() {
+        // nobody can take the seven of clubs
+        let cards = stack("S7 C7");
+        let weight = |_: usize, card: Card| if card.suit() == Suit::Clubs { 0. } else { 1. };
+        assert!(sample_deal(cards, [1, 1, 0], weight).is_none());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{round::Round, suit::Suit};
+
+#[derive(Clone, Copy, Debug)]
+pub struct RoundSummary {
+    pub dealer: usize,
+    pub trump: Option<Suit>,
+    pub multiplier: i16,
+    /// the card points each team collected
+    pub card_scores: [i16; 2],
+    pub winning_team: usize,
+    pub points: i16,
+}
+
+/// keeps track of the score of an entire game, i.e. multiple rounds
+#[derive(Default, Clone, Debug)]
+pub struct Scoreboard {
+    scores: [i16; 2],
+    history: Vec<RoundSummary>,
+}
+
+impl Scoreboard {
+    /// add the points of a finished round
+    pub fn record(&mut self, round: &Round) {
+        let (winning_team, points) = round.outcome();
+        self.scores[winning_team] += points;
+
+        self.history.push(RoundSummary {
+            dealer: round.dealer(),
+            trump: round.trump(),
+            multiplier: round.multiplier(),
+            card_scores: round.scores(),
+            winning_team,
+            points,
+        });
+    }
+
+    pub fn is_finished(&self, target_score: i16) -> bool {
+        self.scores.iter().any(|&s| s >= target_score)
+    }
+
+    pub fn winner(&self) -> usize {
+        self.scores
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, s)| **s)
+            .unwrap()
+            .0
+    }
+
+    pub const fn scores(&self) -> [i16; 2] {
+        self.scores
+    }
+
+    pub fn history(&self) -> &[RoundSummary] {
+        &self.history
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        self.history.len()
+    }
+}
@@ -0,0 +1,48 @@
+use crate::suit::Suit;
+
+/// how many times the points of a round count, depending on trump
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scoring {
+    /// indexed by suit, see [`Suit`]
+    pub trump_multipliers: [i16; 4],
+    pub no_trump_multiplier: i16,
+}
+
+impl Scoring {
+    pub const fn multiplier(&self, trump: Option<Suit>) -> i16 {
+        match trump {
+            Some(suit) => self.trump_multipliers[suit as usize],
+            None => self.no_trump_multiplier,
+        }
+    }
+
+    /// the multiplier of the trump that counts the most
+    pub fn max_multiplier(&self) -> i16 {
+        self.trump_multipliers
+            .into_iter()
+            .fold(self.no_trump_multiplier, i16::max)
+    }
+
+    /// every round counts the same
+    pub const fn flat() -> Self {
+        Scoring {
+            trump_multipliers: [1; 4],
+            no_trump_multiplier: 1,
+        }
+    }
+
+    /// playing without trump counts double
+    pub const fn double_no_trump() -> Self {
+        Scoring {
+            no_trump_multiplier: 2,
+            ..Scoring::flat()
+        }
+    }
+}
+
+impl Default for Scoring {
+    /// flat, the rules that double rounds without trump say so
+    fn default() -> Self {
+        Scoring::flat()
+    }
+}
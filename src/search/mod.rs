@@ -0,0 +1,7 @@
+//! information set monte carlo tree search, ported from the `ismcts` crate
+//! so the search loop can be driven by budgets other than wall clock time.
+//! the `State` and `ActionList` traits are still the ones from `ismcts`
+pub(crate) mod edge;
+pub mod node;
+pub mod searcher;
+pub(crate) mod tree;
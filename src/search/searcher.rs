@@ -0,0 +1,423 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use ismcts::state::State;
+
+use super::{node::NodeStats, tree::Tree};
+
+/// pondering stops at this tree size, unless the searcher has its own limit,
+/// as there's no telling how long the other player will take. It holds for
+/// every searching thread, so keep it to tens of megabytes per tree
+const MAX_PONDER_NODES: usize = 200_000;
+
+/// how long a search is allowed to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// wall clock time in milliseconds
+    Time(u128),
+    /// a fixed number of simulations, independent of machine speed and load
+    Simulations(usize),
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Budget::Time(time) => write!(f, "{time}ms"),
+            Budget::Simulations(num_sims) => write!(f, "{num_sims}sims"),
+        }
+    }
+}
+
+impl Budget {
+    /// the budget of search `index` out of `num_searches` running in parallel.
+    /// They all get the full time, simulations are divided among them
+    pub fn share(self, index: usize, num_searches: usize) -> Budget {
+        match self {
+            Budget::Time(_) => self,
+            Budget::Simulations(num_sims) => {
+                Budget::Simulations(share(num_sims, index, num_searches))
+            }
+        }
+    }
+}
+
+/// the part of `total` for share `index` out of `num_shares`, as even as possible
+pub const fn share(total: usize, index: usize, num_shares: usize) -> usize {
+    let extra = (index < total % num_shares) as usize;
+    total / num_shares + extra
+}
+
+/// parses `200ms` or `5000sims`, a plain number is a time in milliseconds
+impl FromStr for Budget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid budget '{s}', expected e.g. 200ms or 5000sims");
+
+        if let Some(num_sims) = s.strip_suffix("sims") {
+            return num_sims
+                .parse()
+                .map(Budget::Simulations)
+                .map_err(|_| error());
+        }
+
+        s.strip_suffix("ms")
+            .unwrap_or(s)
+            .parse()
+            .map(Budget::Time)
+            .map_err(|_| error())
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct SearchResult<T: State> {
+    pub num_simulations: usize,
+    pub duration: Duration,
+    pub best_action: Option<T::Action>,
+    pub child_stats: Vec<(NodeStats, T::Action)>,
+    pub tree_size: usize,
+    /// the position was solved exactly instead of searched
+    pub solved: bool,
+}
+
+impl<T: State> SearchResult<T> {
+    /// combines independent searches of the same position, the statistics of
+    /// every action are summed and the most simulated action is the best one
+    pub fn merge(results: impl IntoIterator<Item = Self>) -> Self {
+        let mut merged = SearchResult {
+            num_simulations: 0,
+            duration: Duration::default(),
+            best_action: None,
+            child_stats: vec![],
+            tree_size: 0,
+            solved: false,
+        };
+
+        for result in results {
+            merged.num_simulations += result.num_simulations;
+            merged.duration = merged.duration.max(result.duration);
+            merged.tree_size += result.tree_size;
+
+            for (stats, action) in result.child_stats {
+                match merged.child_stats.iter_mut().find(|(_, a)| *a == action) {
+                    Some((merged_stats, _)) => {
+                        let num_sims = merged_stats.num_sims + stats.num_sims;
+                        merged_stats.avg_score = (merged_stats.avg_score
+                            * merged_stats.num_sims as f32
+                            + stats.avg_score * stats.num_sims as f32)
+                            / num_sims as f32;
+                        merged_stats.num_sims = num_sims;
+                    }
+                    None => merged.child_stats.push((stats, action)),
+                }
+            }
+        }
+
+        merged.best_action = merged
+            .child_stats
+            .iter()
+            .max_by_key(|(stats, _)| stats.num_sims)
+            .map(|(_, action): &(NodeStats, T::Action)| action.clone());
+        merged
+    }
+}
+
+/// plays a state out to the end, once the search leaves the tree
+pub type Rollout<T> = fn(&mut T);
+
+#[derive(Clone)]
+pub struct Searcher<T: State + Clone> {
+    tree: Tree<T>,
+    /// stops the search early once the tree holds this many nodes
+    max_nodes: Option<usize>,
+    rollout: Rollout<T>,
+}
+
+impl<T: State + Clone> Searcher<T> {
+    pub fn new(c: f32, default_capacity: usize) -> Self {
+        Searcher {
+            tree: Tree::new(c, default_capacity),
+            max_nodes: None,
+            rollout: T::do_rollout,
+        }
+    }
+
+    pub fn set_max_nodes(&mut self, max_nodes: Option<usize>) {
+        self.max_nodes = max_nodes;
+    }
+
+    /// the exploration constant of the uct formula
+    pub const fn set_exploration(&mut self, c: f32) {
+        self.tree.set_exploration(c);
+    }
+
+    /// replaces the uniformly random playouts
+    pub fn set_rollout(&mut self, rollout: Rollout<T>) {
+        self.rollout = rollout;
+    }
+
+    /// forget the current tree, so the next search starts from scratch
+    pub fn reset(&mut self) {
+        self.tree.reset();
+    }
+
+    /// moves the root along `actions`, keeping the statistics of the subtree
+    /// that's left. Returns false and clears the tree if an action was never tried
+    pub fn advance(&mut self, actions: &[T::Action]) -> bool {
+        let mut node_id = 0;
+        for action in actions {
+            match (self.tree.get_size() > 0)
+                .then(|| self.tree.find_child(node_id, action))
+                .flatten()
+            {
+                Some(child_id) => node_id = child_id,
+                None => {
+                    self.tree.reset();
+                    return false;
+                }
+            }
+        }
+
+        if node_id != 0 {
+            self.tree.retain_subtree(node_id);
+        }
+        true
+    }
+
+    /// continues searching the current tree, whose root should be `state`
+    pub fn search(
+        &mut self,
+        state: &T,
+        inference: &T::Inference,
+        budget: Budget,
+    ) -> SearchResult<T> {
+        let root_id = self.root_id();
+        let mut i = 0;
+        let started = Instant::now();
+
+        // always run at least one simulation, so there is a best action
+        while i == 0 || !self.is_exhausted(budget, i, started) {
+            self.simulate(root_id, state, state.turn(), inference);
+            i += 1;
+        }
+
+        SearchResult {
+            num_simulations: i,
+            duration: started.elapsed(),
+            best_action: self.tree.best_action(root_id, state),
+            child_stats: self.tree.child_stats(root_id, state),
+            tree_size: self.tree.get_size(),
+            solved: false,
+        }
+    }
+
+    /// searches on the turn of another player until `stop` is set, with
+    /// the hidden cards dealt from the point of view of `observer`.
+    /// Returns the number of simulations
+    pub fn ponder(
+        &mut self,
+        state: &T,
+        observer: usize,
+        inference: &T::Inference,
+        stop: &AtomicBool,
+    ) -> usize {
+        let root_id = self.root_id();
+        let max_nodes = self.max_nodes.unwrap_or(MAX_PONDER_NODES);
+        let mut i = 0;
+
+        while !stop.load(Ordering::Relaxed) && self.tree.get_size() < max_nodes {
+            self.simulate(root_id, state, observer, inference);
+            i += 1;
+        }
+
+        i
+    }
+
+    fn root_id(&mut self) -> usize {
+        if self.tree.get_size() == 0 {
+            self.tree.add_node(None, None);
+        }
+        0
+    }
+
+    fn simulate(&mut self, root_id: usize, state: &T, observer: usize, inference: &T::Inference) {
+        let mut state = state.randomize(observer, inference);
+
+        let node_id = self.tree.select(root_id, &mut state);
+        let node_id = self.tree.expand(node_id, &mut state);
+        (self.rollout)(&mut state);
+        self.backpropagate(&state, node_id);
+    }
+
+    fn is_exhausted(&self, budget: Budget, num_sims: usize, started: Instant) -> bool {
+        if self
+            .max_nodes
+            .is_some_and(|max_nodes| self.tree.get_size() >= max_nodes)
+        {
+            return true;
+        }
+
+        match budget {
+            Budget::Time(time) => {
+                num_sims.is_multiple_of(2048) && started.elapsed().as_millis() > time
+            }
+            Budget::Simulations(max_sims) => num_sims >= max_sims,
+        }
+    }
+
+    fn backpropagate(&mut self, state: &T, node_id: usize) {
+        let mut node_id = Some(node_id);
+
+        while let Some(id) = node_id {
+            if let Some(edge) = self.tree.get_edge(id) {
+                self.tree.update_node(id, state.reward(edge.actor()));
+            }
+            node_id = self.tree.get_parent_id(id);
+        }
+    }
+}
+
+impl<T: State + Clone> Default for Searcher<T> {
+    fn default() -> Self {
+        Self::new(2f32.sqrt(), 500_000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::{Budget, Rollout, Searcher};
+    use crate::{
+        action::Action, inference::Inference, params::Params, players::heuristic_player, random,
+        round::Round,
+    };
+
+    #[test]
+    fn test_parse_budget() {
+        assert_eq!("200ms".parse(), Ok(Budget::Time(200)));
+        assert_eq!("200".parse(), Ok(Budget::Time(200)));
+        assert_eq!("5000sims".parse(), Ok(Budget::Simulations(5000)));
+        assert!("fast".parse::<Budget>().is_err());
+
+        for budget in [Budget::Time(150), Budget::Simulations(300)] {
+            assert_eq!(budget.to_string().parse(), Ok(budget));
+        }
+    }
+
+    #[test]
+    fn test_simulation_budget() {
+        let search = |max_nodes| {
+            random::seed(Some(7));
+            let round = Round::new(0);
+            let mut searcher = Searcher::default();
+            searcher.set_max_nodes(max_nodes);
+            searcher.search(&round, &Inference::default(), Budget::Simulations(1000))
+        };
+
+        let result = search(None);
+        assert_eq!(result.num_simulations, 1000);
+        assert!(result.best_action.is_some());
+
+        // a fixed number of simulations is reproducible
+        let sims = |result: &super::SearchResult<Round>| {
+            result
+                .child_stats
+                .iter()
+                .map(|(stats, _)| stats.num_sims)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sims(&result), sims(&search(None)));
+
+        let result = search(Some(100));
+        assert_eq!(result.tree_size, 100);
+        assert!(result.num_simulations < 1000);
+    }
+
+    #[test]
+    fn test_heuristic_rollout() {
+        // plays a round with a new searcher for every action, returns the
+        // average scores of the first search
+        let play = |rollout: Rollout<Round>| {
+            random::seed(Some(21));
+            let mut round = Round::new(0);
+            let mut inference = Inference::new(Params::default());
+            let mut first_scores = None;
+
+            while !round.is_terminal() {
+                let mut searcher = Searcher::default();
+                searcher.set_rollout(rollout);
+                let result = searcher.search(&round, &inference, Budget::Simulations(200));
+
+                let scores = result.child_stats.iter().map(|(stats, _)| stats.avg_score);
+                assert!(scores.clone().all(|score| (-1. ..=1.).contains(&score)));
+                first_scores.get_or_insert_with(|| scores.collect::<Vec<_>>());
+
+                let action = result.best_action.unwrap();
+                assert!(round.possible_actions().has(&action));
+                inference.infer(&round, action, round.turn());
+                round.apply_action(action);
+            }
+
+            assert_eq!(round.played_cards().len(), 32);
+            first_scores.unwrap()
+        };
+
+        // the same deal and simulations, only the playouts differ
+        assert_ne!(play(heuristic_player::rollout), play(Round::do_rollout));
+    }
+
+    #[test]
+    fn test_merge() {
+        random::seed(Some(8));
+        let round = Round::new(0);
+        let results = (0..3).map(|i| {
+            let budget = Budget::Simulations(1000).share(i, 3);
+            Searcher::default().search(&round, &Inference::default(), budget)
+        });
+
+        let merged = super::SearchResult::merge(results);
+        assert_eq!(merged.num_simulations, 1000);
+        // every simulation passes through one child of the root
+        let num_sims = merged.child_stats.iter().map(|(stats, _)| stats.num_sims);
+        assert_eq!(num_sims.clone().sum::<usize>(), 1000);
+        assert_eq!(
+            merged
+                .child_stats
+                .iter()
+                .find(|(_, action)| Some(*action) == merged.best_action)
+                .map(|(stats, _)| stats.num_sims),
+            num_sims.max()
+        );
+    }
+
+    #[test]
+    fn test_advance() {
+        random::seed(Some(4));
+        let mut round = Round::new(0);
+        let mut searcher = Searcher::default();
+        let inference = Inference::default();
+
+        let result = searcher.search(&round, &inference, Budget::Simulations(2000));
+        let action = result.best_action.unwrap();
+        assert!(searcher.advance(&[action]));
+
+        // the subtree is kept and searched further, a new tree would have 101 nodes
+        round.apply_action(action);
+        let result = searcher.search(&round, &inference, Budget::Simulations(100));
+        assert!(result.tree_size > 101);
+        assert!(!result.child_stats.is_empty());
+
+        // a line that was never explored starts over, trump can't be passed
+        // once it's picked
+        let action = round.possible_actions().pop_random().unwrap();
+        round.apply_action(action);
+        assert!(!searcher.advance(&[action, Action::PassTrump]));
+        let result = searcher.search(&round, &inference, Budget::Simulations(10));
+        assert_eq!(result.tree_size, 11);
+    }
+}
@@ -0,0 +1,505 @@
+//! exact alpha-beta solver for a round in which all cards are known,
+//! also known as double dummy analysis
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    time::Instant,
+};
+
+use ismcts::state::State;
+
+use crate::{
+    action::Action,
+    action_collection::ActionCollection,
+    card::Card,
+    round::{Round, RoundPhase},
+    search::{node::NodeStats, searcher::SearchResult},
+    stack::Stack,
+    suit::Suit,
+};
+
+/// the team whose points are maximized, the other team minimizes them
+const TEAM_0: usize = 0;
+
+/// the hands and the player to lead, at the start of a trick
+type Key = ([Stack; 4], usize, Option<Suit>);
+
+/// a multiply-rotate hash, the default hasher is a lot slower and
+/// there's no need to protect the table against malicious keys
+#[derive(Default)]
+struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// bounds on the points team 0 earns in the remaining tricks
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    lower: i16,
+    upper: i16,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Solution {
+    /// the final card score of the team to play, i.e. including the
+    /// points they already have
+    pub score: i16,
+    pub best_card: Card,
+}
+
+/// the transposition table is cleared for every solve, as
+/// the same hands rarely show up in two different solves
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<Key, Entry, BuildHasherDefault<KeyHasher>>,
+    num_nodes: usize,
+}
+
+impl Solver {
+    /// `round` should be in the card playing phase
+    pub fn solve(&mut self, round: &Round) -> Solution {
+        self.reset(round);
+
+        let maximize = round.turn() % 2 == TEAM_0;
+        let mut best = None;
+        let (mut alpha, mut beta) = (-1, 61);
+
+        for card in ordered_cards(round) {
+            let (gain, next) = play(round, card);
+            let value = gain + self.search(&next, alpha - gain, beta - gain);
+
+            if maximize && value > alpha {
+                alpha = value;
+                best = Some((value, card));
+            } else if !maximize && value < beta {
+                beta = value;
+                best = Some((value, card));
+            }
+        }
+
+        let (value, best_card) = best.unwrap();
+
+        Solution {
+            score: final_score(round, value),
+            best_card,
+        }
+    }
+
+    /// the final card score of the team to play for every legal card,
+    /// this is slower than [`Solver::solve`] as every card is solved exactly
+    pub fn card_scores(&mut self, round: &Round) -> Vec<(Card, i16)> {
+        self.reset(round);
+
+        let ActionCollection::Cards(legal) = round.possible_actions() else {
+            unreachable!()
+        };
+
+        legal
+            .into_iter()
+            .map(|card| {
+                let (gain, next) = play(round, card);
+                let value = gain + self.search(&next, -1, 61);
+                (card, final_score(round, value))
+            })
+            .collect()
+    }
+
+    /// [`Solver::card_scores`] averaged over `deals` of the same position,
+    /// weighted by their likelihood
+    pub fn expected_card_scores(
+        &mut self,
+        deals: impl IntoIterator<Item = (Round, f64)>,
+    ) -> Vec<(Card, f64)> {
+        // the legal cards only depend on the hand of the player to play,
+        // so they're the same, in the same order, for every deal
+        let mut totals: Vec<(Card, f64)> = vec![];
+        let mut total_likelihood = 0.;
+
+        for (deal, likelihood) in deals {
+            let scores = self.card_scores(&deal);
+            if totals.is_empty() {
+                totals = scores.iter().map(|&(card, _)| (card, 0.)).collect();
+            }
+            for ((_, total), (_, score)) in totals.iter_mut().zip(scores) {
+                *total += likelihood * score as f64;
+            }
+            total_likelihood += likelihood;
+        }
+
+        for (_, total) in &mut totals {
+            *total /= total_likelihood;
+        }
+        totals
+    }
+
+    fn reset(&mut self, round: &Round) {
+        assert!(round.phase() == RoundPhase::PlayCards && !round.is_terminal());
+
+        self.table.clear();
+        self.num_nodes = 0;
+    }
+
+    /// number of positions visited by the last solve
+    pub const fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    /// the points team 0 earns from here on
+    fn search(&mut self, round: &Round, mut alpha: i16, mut beta: i16) -> i16 {
+        self.num_nodes += 1;
+
+        if round.is_terminal() {
+            return 0;
+        }
+
+        let key = (round.trick_ref().cards().len() == 0).then(|| key(round));
+        if let Some(entry) = key.and_then(|key| self.table.get(&key)) {
+            if entry.lower >= beta {
+                return entry.lower;
+            }
+            if entry.upper <= alpha {
+                return entry.upper;
+            }
+            alpha = alpha.max(entry.lower);
+            beta = beta.min(entry.upper);
+            // an exact value inside the window, searching on would only
+            // return a bound on it
+            if alpha >= beta {
+                return entry.lower;
+            }
+        }
+
+        let (original_alpha, original_beta) = (alpha, beta);
+        let maximize = round.turn() % 2 == TEAM_0;
+        let mut best = if maximize { -1 } else { 61 };
+
+        for card in ordered_cards(round) {
+            let (gain, next) = play(round, card);
+            let value = gain + self.search(&next, alpha - gain, beta - gain);
+
+            if maximize {
+                best = best.max(value);
+                alpha = alpha.max(value);
+            } else {
+                best = best.min(value);
+                beta = beta.min(value);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(key) = key {
+            let entry = self.table.entry(key).or_insert(Entry {
+                lower: 0,
+                upper: 60,
+            });
+            if best <= original_alpha {
+                entry.upper = entry.upper.min(best);
+            } else if best >= original_beta {
+                entry.lower = entry.lower.max(best);
+            } else {
+                *entry = Entry {
+                    lower: best,
+                    upper: best,
+                };
+            }
+            debug_assert!(entry.lower <= entry.upper, "crossed bounds {entry:?}");
+        }
+
+        best
+    }
+}
+
+/// the expected card scores of solved deals as the result of a search,
+/// with the best card as the best action
+pub fn card_search_result(
+    round: &Round,
+    scores: Vec<(Card, f64)>,
+    num_deals: usize,
+    started: Instant,
+    solved: bool,
+) -> SearchResult<Round> {
+    let child_stats = scores
+        .into_iter()
+        .map(|(card, score)| {
+            let stats = NodeStats {
+                // the reward of a round, but with the expected card score
+                avg_score: round.reward_of(score as f32),
+                num_sims: num_deals,
+            };
+            (stats, Action::PlayCard(card))
+        })
+        .collect::<Vec<_>>();
+
+    let best_action = child_stats
+        .iter()
+        .max_by(|(a, _), (b, _)| a.avg_score.total_cmp(&b.avg_score))
+        .map(|&(_, action)| action);
+
+    SearchResult {
+        num_simulations: num_deals,
+        duration: started.elapsed(),
+        best_action,
+        child_stats,
+        tree_size: 0,
+        solved,
+    }
+}
+
+/// the final card score of the team to play, given that team 0 earns
+/// `value` points from here on
+fn final_score(round: &Round, value: i16) -> i16 {
+    let scores = round.scores();
+    let remaining = 60 - scores[0] - scores[1];
+
+    if round.turn() % 2 == TEAM_0 {
+        scores[0] + value
+    } else {
+        scores[1] + remaining - value
+    }
+}
+
+fn key(round: &Round) -> Key {
+    let hands = std::array::from_fn(|i| round.player_cards(i));
+    (hands, round.turn(), round.trump())
+}
+
+/// plays `card` and returns the points team 0 earned by it
+fn play(round: &Round, card: Card) -> (i16, Round) {
+    let mut next = *round;
+    next.apply_action(Action::PlayCard(card));
+
+    (next.scores()[0] - round.scores()[0], next)
+}
+
+/// the legal cards, those that take the lead first. Cards without points
+/// that are next to each other, once the cards of earlier tricks are
+/// left out, are equivalent and only one of them is kept
+fn ordered_cards(round: &Round) -> Vec<Card> {
+    let ActionCollection::Cards(legal) = round.possible_actions() else {
+        unreachable!()
+    };
+
+    let player = round.turn();
+    let hand = round.player_cards(player);
+    // the cards of the current trick still decide who wins it
+    let mut others = round.unplayed_cards() & !hand;
+    for &card in round.trick_ref().cards().iter() {
+        others.push(card);
+    }
+
+    let mut cards = legal
+        .into_iter()
+        .filter(|&card| {
+            let below = hand.of_suit(card.suit()).below(card);
+            match below.highest_of_suit(card.suit()) {
+                Some(lower) if card.score() == 0 && legal.has_card(lower) => {
+                    // keep it if an unplayed card of another player lies in between
+                    (others.of_suit(card.suit()).above(lower).below(card)) != 0
+                }
+                _ => true,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let takes_lead = |card: &Card| {
+        let mut trick = *round.trick_ref();
+        trick.play(*card, player);
+        trick.winner().unwrap().1 == player
+    };
+    cards.sort_by_key(|card| (!takes_lead(card), std::cmp::Reverse(card.value())));
+
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use ismcts::{action_list::ActionList, state::State};
+
+    use super::{Solver, TEAM_0};
+    use crate::{
+        action::Action, action_collection::ActionCollection, card::Card, inference::Inference,
+        random, round::Round, rules::Rules, stack::Stack,
+    };
+
+    fn stack(cards: &str) -> Stack {
+        let mut stack = Stack::default();
+        for card in cards.split_whitespace() {
+            stack.push(card.parse::<Card>().unwrap());
+        }
+        stack
+    }
+
+    /// plain minimax, the points team 0 earns from here on
+    fn minimax(round: &Round) -> i16 {
+        if round.is_terminal() {
+            return 0;
+        }
+
+        let ActionCollection::Cards(cards) = round.possible_actions() else {
+            unreachable!()
+        };
+        let values = cards.into_iter().map(|card| {
+            let mut next = *round;
+            next.apply_action(Action::PlayCard(card));
+            next.scores()[0] - round.scores()[0] + minimax(&next)
+        });
+
+        if round.turn() % 2 == TEAM_0 {
+            values.max().unwrap()
+        } else {
+            values.min().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_matches_minimax() {
+        random::seed(Some(5));
+        let mut solver = Solver::default();
+
+        for i in 0..400u32 {
+            // play randomly until 3 or 4 tricks are left, at the start
+            // of a trick as well as in the middle of one
+            let mut round = Round::new(i as usize % 4);
+            while round.played_cards().len() < 17 + i % 4 {
+                let action = round.possible_actions().pop_random().unwrap();
+                round.apply_action(action);
+            }
+
+            let solution = solver.solve(&round);
+            let team = round.turn() % 2;
+            let remaining = 60 - round.scores()[0] - round.scores()[1];
+            let expected = match team {
+                0 => round.scores()[0] + minimax(&round),
+                _ => round.scores()[1] + remaining - minimax(&round),
+            };
+            assert_eq!(solution.score, expected);
+
+            // the best card achieves that score
+            let mut next = round;
+            next.apply_action(Action::PlayCard(solution.best_card));
+            let value = round.scores()[team]
+                + match team {
+                    0 => next.scores()[0] - round.scores()[0] + minimax(&next),
+                    _ => remaining - (next.scores()[0] - round.scores()[0]) - minimax(&next),
+                };
+            assert_eq!(value, expected);
+
+            // every card is solved exactly, not just the best one
+            for (card, score) in solver.card_scores(&round) {
+                let mut next = round;
+                next.apply_action(Action::PlayCard(card));
+                let value = next.scores()[0] - round.scores()[0] + minimax(&next);
+                let expected = match team {
+                    0 => round.scores()[0] + value,
+                    _ => round.scores()[1] + remaining - value,
+                };
+                assert_eq!(score, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trick_cards_separate() {
+        // five tricks of clubs, diamonds and high hearts are played first
+        let early = [
+            "C7 C8 D7 D8 HQ",
+            "C9 CJ D9 DJ HK",
+            "CQ CK DQ DK HA",
+            "CA C10 DA D10 H10",
+        ]
+        .map(stack);
+        let late = ["H7 H9 HJ", "S7 S9 SQ", "SJ SK H8", "S8 SA S10"].map(stack);
+        let hands = std::array::from_fn(|i| early[i] | late[i]);
+
+        let mut round = Round::from_deal(3, hands, Rules::default());
+        round.apply_action(Action::PickTrump(None));
+        round.apply_action(Action::Pass);
+        round.apply_action(Action::Pass);
+        while round.played_cards().len() < 20 {
+            let ActionCollection::Cards(cards) = round.possible_actions() else {
+                unreachable!()
+            };
+            let card = cards
+                .into_iter()
+                .find(|&card| early[round.turn()].has_card(card));
+            round.apply_action(Action::PlayCard(card.unwrap()));
+        }
+
+        // the partner of player 1 leads ♠8, which lies between their ♠7 and ♠9
+        assert_eq!(round.turn(), 3);
+        round.apply_action(Action::PlayCard("S8".parse().unwrap()));
+        round.apply_action(Action::PlayCard("HJ".parse().unwrap()));
+
+        let remaining = 60 - round.scores()[0] - round.scores()[1];
+        let expected = round.scores()[1] + remaining - minimax(&round);
+        assert_eq!(Solver::default().solve(&round).score, expected);
+    }
+
+    #[test]
+    fn test_determinizations() {
+        random::seed(Some(2));
+        let mut round = Round::new(0);
+        while round.played_cards().len() < 20 {
+            let action = round.possible_actions().pop_random().unwrap();
+            round.apply_action(action);
+        }
+
+        // 9 hidden cards, 3 for every other player
+        let player = round.turn();
+        let deals = round.determinizations(player, &Inference::default());
+        assert_eq!(deals.len(), 1680);
+        assert!(deals
+            .iter()
+            .all(|(deal, _)| (0..4).all(|i| deal.player_cards(i).len() == 3)
+                && deal.player_cards(player) == round.player_cards(player)));
+        assert!(deals
+            .iter()
+            .any(|(deal, _)| (0..4).all(|i| deal.player_cards(i) == round.player_cards(i))));
+
+        // the card scores of a single deal don't change
+        let mut solver = Solver::default();
+        let expected = solver
+            .card_scores(&round)
+            .into_iter()
+            .map(|(card, score)| (card, score as f64))
+            .collect::<Vec<_>>();
+        assert_eq!(solver.expected_card_scores([(round, 0.3)]), expected);
+    }
+
+    #[test]
+    fn test_full_deal() {
+        random::seed(Some(9));
+        let mut round = Round::new(0);
+        round.apply_action(Action::PickTrump(None));
+        while !matches!(round.possible_actions(), ActionCollection::Cards(_)) {
+            round.apply_action(Action::Pass);
+        }
+
+        let solution = Solver::default().solve(&round);
+        assert!((0..=60).contains(&solution.score));
+    }
+}
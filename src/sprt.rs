@@ -1,61 +1,225 @@
-//! NOTE: this is a very crude SPRT-like method,
-//! which might very well be totally incorrect
-use std::sync::{Arc, Mutex};
+//! sequential probability ratio test between two teams of players.
+//! every deal is played twice with the teams swapping seats, and the
+//! log-likelihood ratio is computed on the pair scores using the
+//! normal approximation of the generalized SPRT
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
 
-use crate::{game::Game, players::PlayerVec};
+use crate::{players::PlayerVec, random, rules::Rules, tournament::play_pair};
 
-pub fn run_sprt(num_threads: usize, player_gen: impl Fn() -> PlayerVec + Clone + Send + Sync) {
-    let num_wins = Arc::new(Mutex::new([0.; 2]));
-    let num_games = Arc::new(Mutex::new(0.));
+#[derive(Debug, Clone, Copy)]
+pub struct SprtConfig {
+    /// elo difference of the null hypothesis
+    pub elo0: f64,
+    /// elo difference of the alternative hypothesis
+    pub elo1: f64,
+    /// probability of accepting H1 while H0 is true
+    pub alpha: f64,
+    /// probability of accepting H0 while H1 is true
+    pub beta: f64,
+}
 
-    std::thread::scope(|s| {
-        for _ in 0..num_threads {
-            let player_gen = player_gen.clone();
-            let num_wins = Arc::clone(&num_wins);
-            let num_games = Arc::clone(&num_games);
+impl SprtConfig {
+    fn lower_bound(&self) -> f64 {
+        (self.beta / (1. - self.alpha)).ln()
+    }
 
-            s.spawn(move || loop {
-                let mut game = Game::new(player_gen());
-                while !game.is_terminal() {
-                    game.play_round();
-                }
+    fn upper_bound(&self) -> f64 {
+        ((1. - self.beta) / self.alpha).ln()
+    }
+}
 
-                num_wins.lock().unwrap()[game.winner()] += 1.;
-                *num_games.lock().unwrap() += 1.;
+impl Default for SprtConfig {
+    fn default() -> Self {
+        SprtConfig {
+            elo0: 0.,
+            elo1: 10.,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
 
-                let w = *num_wins.lock().unwrap();
-                let n = *num_games.lock().unwrap();
+/// number of game pairs in which team a won 0, 1 or 2 games.
+/// manille has no draws, so these are the only possible pair outcomes
+#[derive(Debug, Default, Clone, Copy)]
+struct PairStats {
+    counts: [usize; 3],
+}
 
-                let ci_a = confidence_interval(w[0], n);
-                let ci_b = confidence_interval(w[1], n);
+impl PairStats {
+    fn num_pairs(&self) -> usize {
+        self.counts.iter().sum()
+    }
 
-                println!("win rates: a={}%, b={}%", w[0] / n * 100., w[1] / n * 100.);
-                let mean = w[0] / n;
-                let variance = mean * (1. - mean) / n;
-                println!("sigma: {}", variance.sqrt());
+    /// mean and variance of the score per pair, scaled to a single game
+    fn score(&self) -> (f64, f64) {
+        let n = self.num_pairs() as f64;
+        let scores = [0., 0.5, 1.];
 
-                if n < 100. {
-                    continue;
-                }
+        let mean = self
+            .counts
+            .iter()
+            .zip(scores)
+            .map(|(&count, score)| count as f64 * score)
+            .sum::<f64>()
+            / n;
+        let variance = self
+            .counts
+            .iter()
+            .zip(scores)
+            .map(|(&count, score)| count as f64 * (score - mean).powi(2))
+            .sum::<f64>()
+            / n;
 
-                if ci_a.0 > ci_b.1 {
-                    break Some(0);
-                } else if ci_b.0 > ci_a.1 {
-                    break Some(1);
+        (mean, variance)
+    }
+
+    fn llr(&self, config: &SprtConfig) -> f64 {
+        let (mean, variance) = self.score();
+        if self.num_pairs() == 0 || variance == 0. {
+            return 0.;
+        }
+
+        let s0 = elo_to_score(config.elo0);
+        let s1 = elo_to_score(config.elo1);
+
+        self.num_pairs() as f64 * (s1 - s0) * (2. * mean - s0 - s1) / (2. * variance)
+    }
+
+    /// elo estimate of team a with a 95% confidence interval
+    fn elo(&self) -> (f64, f64, f64) {
+        let (mean, variance) = self.score();
+        let margin = 1.96 * (variance / self.num_pairs() as f64).sqrt();
+
+        (
+            score_to_elo(mean),
+            score_to_elo(mean - margin),
+            score_to_elo(mean + margin),
+        )
+    }
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1. - 1e-6);
+    -400. * (1. / score - 1.).log10()
+}
+
+/// plays game pairs until the test accepts one of the hypotheses.
+/// team a are the players on seats 0 and 2 of `player_gen`
+pub fn run_sprt(
+    num_threads: usize,
+    rules: Rules,
+    config: SprtConfig,
+    player_gen: impl Fn() -> PlayerVec + Clone + Send + Sync,
+) {
+    println!(
+        "sprt elo0={} elo1={} alpha={} beta={}, bounds [{:.2}, {:.2}]",
+        config.elo0,
+        config.elo1,
+        config.alpha,
+        config.beta,
+        config.lower_bound(),
+        config.upper_bound()
+    );
+
+    let stats = Arc::new(Mutex::new(PairStats::default()));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    std::thread::scope(|s| {
+        for _ in 0..num_threads {
+            let player_gen = player_gen.clone();
+            let stats = Arc::clone(&stats);
+            let stop = Arc::clone(&stop);
+            let seed = random::thread_seed();
+
+            s.spawn(move || {
+                random::seed(Some(seed));
+                while !stop.load(Ordering::Acquire) {
+                    let num_wins = play_pair(rules, false, &player_gen)
+                        .iter()
+                        .filter(|(game, team)| game.winner() == *team)
+                        .count();
+
+                    let current = {
+                        let mut stats = stats.lock().unwrap();
+                        // pairs finishing after the decision don't count
+                        if stop.load(Ordering::Acquire) {
+                            break;
+                        }
+                        stats.counts[num_wins] += 1;
+
+                        let llr = stats.llr(&config);
+                        if llr <= config.lower_bound() || llr >= config.upper_bound() {
+                            stop.store(true, Ordering::Release);
+                        }
+                        *stats
+                    };
+
+                    print_status(&current, &config);
                 }
             });
         }
     });
 
-    println!("games: {:?}", num_games.lock().unwrap());
-    println!("wins: {:?}", num_wins.lock().unwrap());
+    let stats = *stats.lock().unwrap();
+    let llr = stats.llr(&config);
+    if llr >= config.upper_bound() {
+        println!(
+            "H1 accepted: team a is at least {} elo stronger",
+            config.elo1
+        );
+    } else {
+        println!(
+            "H0 accepted: team a is at most {} elo stronger",
+            config.elo0
+        );
+    }
+    print_status(&stats, &config);
 }
 
-fn confidence_interval(num_wins: f32, num_games: f32) -> (f32, f32) {
-    const Z: f32 = 3.;
-    let mean = num_wins / num_games;
-    let variance = mean * (1. - mean) / num_games;
-    let sigma = variance.sqrt();
+fn print_status(stats: &PairStats, config: &SprtConfig) {
+    let (elo, lower, upper) = stats.elo();
+
+    println!(
+        "pairs: {} {:?}, elo: {elo:.1} [{lower:.1}, {upper:.1}], llr: {:.2} [{:.2}, {:.2}]",
+        stats.num_pairs(),
+        stats.counts,
+        stats.llr(config),
+        config.lower_bound(),
+        config.upper_bound()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elo_to_score, score_to_elo, PairStats, SprtConfig};
+
+    #[test]
+    fn test_llr() {
+        let config = SprtConfig::default();
+        assert!((score_to_elo(elo_to_score(35.)) - 35.).abs() < 1e-6);
+
+        // an even match favours H0, a lopsided one H1
+        let even = PairStats {
+            counts: [250, 500, 250],
+        };
+        assert!(even.llr(&config) < 0.);
+        assert!(even.elo().0.abs() < 1e-6);
+
+        let lopsided = PairStats {
+            counts: [200, 500, 300],
+        };
+        assert!(lopsided.llr(&config) > config.upper_bound());
 
-    (mean - Z * sigma, mean + Z * sigma)
+        let (elo, lower, upper) = lopsided.elo();
+        assert!(lower < elo && elo < upper);
+        assert!(lower > 0.);
+    }
 }
@@ -0,0 +1,178 @@
+//! tunes the [`Params`] of an mcts player with simultaneous perturbation
+//! stochastic approximation. Every iteration moves all tunables at once in a
+//! random direction, plays duplicate pairs between the params moved one way and
+//! the params moved the other way, and steps towards the side that won. The step
+//! and the perturbation shrink over the iterations, so the params settle down
+use std::{
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    params::{Params, TUNABLES},
+    players::spec::PlayerSpec,
+    random,
+    rules::Rules,
+    tournament::play_pair,
+};
+
+/// the first step when one side won every game, in units of [`Tunable::step`]
+///
+/// [`Tunable::step`]: crate::params::Tunable::step
+const LEARNING_RATE: f64 = 1.;
+/// the usual decay exponents of the step and the perturbation
+const ALPHA: f64 = 0.602;
+const GAMMA: f64 = 0.101;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpsaConfig {
+    pub num_iterations: usize,
+    /// the duplicate pairs played every iteration
+    pub num_pairs: usize,
+    pub num_threads: usize,
+}
+
+type Values = [f64; TUNABLES.len()];
+
+/// the perturbation and the step size of iteration `k`, relative to the first iteration
+fn gains(k: usize, num_iterations: usize) -> (f64, f64) {
+    // keeps the step from shrinking too fast in the first iterations
+    let stability = num_iterations as f64 / 10.;
+    let k = k as f64;
+
+    let c = 1. / (k + 1.).powf(GAMMA);
+    let a = LEARNING_RATE * ((stability + 1.) / (k + 1. + stability)).powf(ALPHA);
+    (c, a)
+}
+
+fn to_params(values: Values) -> Params {
+    let mut params = Params::default();
+    for (i, tunable) in TUNABLES.iter().enumerate() {
+        *params.value_mut(i) = (values[i] as f32).clamp(tunable.min, tunable.max);
+    }
+    params
+}
+
+fn summary(params: &Params) -> String {
+    TUNABLES
+        .iter()
+        .enumerate()
+        .map(|(i, tunable)| format!("{}={:.3}", tunable.name, params.value(i)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// plays duplicate pairs of `plus` against `minus`, returns the share of the games
+/// `plus` won more than `minus`, from -1 to 1
+fn play_iteration(plus: PlayerSpec, minus: PlayerSpec, rules: Rules, config: SpsaConfig) -> f64 {
+    let wins = AtomicUsize::new(0);
+    let next_pair = AtomicUsize::new(0);
+
+    std::thread::scope(|s| {
+        for _ in 0..config.num_threads {
+            let (wins, next_pair) = (&wins, &next_pair);
+            let seed = random::thread_seed();
+
+            s.spawn(move || {
+                random::seed(Some(seed));
+                while next_pair.fetch_add(1, Ordering::Relaxed) < config.num_pairs {
+                    let specs = [plus, minus, plus, minus];
+                    let player_gen = || specs.iter().map(PlayerSpec::build).collect();
+
+                    for (game, team) in play_pair(rules, false, &player_gen) {
+                        if game.winner() == team {
+                            wins.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let num_games = 2 * config.num_pairs;
+    (2 * wins.into_inner()) as f64 / num_games as f64 - 1.
+}
+
+/// tunes the params of `player`, an mcts player, starting from its own. The params
+/// are written to `output` after every iteration, so an interrupted run isn't lost
+pub fn run_spsa(player: PlayerSpec, rules: Rules, config: SpsaConfig, output: &Path) -> Params {
+    let PlayerSpec::Mcts { params, .. } = player else {
+        panic!("only mcts players have params to tune");
+    };
+    println!(
+        "tuning {player} for {} iterations of {} pairs on {} threads",
+        config.num_iterations, config.num_pairs, config.num_threads
+    );
+
+    let mut values: Values = std::array::from_fn(|i| params.value(i) as f64);
+    for k in 0..config.num_iterations {
+        let (c, a) = gains(k, config.num_iterations);
+        let directions: Values = std::array::from_fn(|_| if romu::bool() { 1. } else { -1. });
+        let perturbed = |sign: f64| {
+            let values = std::array::from_fn(|i| {
+                values[i] + sign * c * directions[i] * TUNABLES[i].step as f64
+            });
+            player.with_params(to_params(values))
+        };
+
+        let result = play_iteration(perturbed(1.), perturbed(-1.), rules, config);
+        for (i, tunable) in TUNABLES.iter().enumerate() {
+            let step = a * result / (2. * c) * directions[i] * tunable.step as f64;
+            values[i] = (values[i] + step).clamp(tunable.min as f64, tunable.max as f64);
+        }
+
+        let params = to_params(values);
+        println!("{}: {result:+.3} {}", k + 1, summary(&params));
+        if let Err(e) = params.save(output) {
+            eprintln!("failed to write {}: {e}", output.display());
+        }
+    }
+
+    println!("tuned params written to {}", output.display());
+    to_params(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gains, run_spsa, SpsaConfig};
+    use crate::{
+        params::{Params, TUNABLES},
+        players::spec::PlayerSpec,
+        random,
+        rules::Rules,
+    };
+
+    #[test]
+    fn test_gains() {
+        let (c, a) = gains(0, 100);
+        assert_eq!((c, a), (1., 1.));
+
+        let (last_c, last_a) = gains(99, 100);
+        assert!(last_c < c && last_a < a);
+        // the perturbation shrinks slower, so late steps stay measurable
+        assert!(last_c > last_a);
+    }
+
+    #[test]
+    fn test_run() {
+        random::seed(Some(25));
+        let player = "mcts:sims=10,exploration=0.3"
+            .parse::<PlayerSpec>()
+            .unwrap();
+        let config = SpsaConfig {
+            num_iterations: 2,
+            num_pairs: 1,
+            num_threads: 1,
+        };
+        // unique, so concurrent test runs don't share the file
+        let output =
+            std::env::temp_dir().join(format!("manille-spsa-test-{}.txt", std::process::id()));
+
+        let params = run_spsa(player, Rules::default(), config, &output);
+        assert_eq!(Params::load(&output), Ok(params));
+        for (i, tunable) in TUNABLES.iter().enumerate() {
+            assert!((tunable.min..=tunable.max).contains(&params.value(i)));
+        }
+        std::fs::remove_file(output).unwrap();
+    }
+}
@@ -17,17 +17,12 @@ pub const ALL: u32 = SPADES | CLUBS | HEARTS | DIAMONDS;
 
 const TENS: u32 = 1 << 7 | 1 << 15 | 1 << 23 | 1 << 31;
 const ACES: u32 = TENS >> 1;
-const KINGS: u32 = TENS >> 2;
-const QUEENS: u32 = TENS >> 3;
-const JACKS: u32 = TENS >> 4;
-const NINES: u32 = TENS >> 5;
-const EIGHTS: u32 = TENS >> 6;
 const SEVENS: u32 = TENS >> 7;
 
 const HIGHEST_CARD: u32 = ACES;
 const LOWEST_CARD: u32 = SEVENS;
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Stack {
     data: u32,
 }
@@ -217,12 +212,6 @@ impl Stack {
     }
 }
 
-impl PartialEq for Stack {
-    fn eq(&self, other: &Self) -> bool {
-        self.data == other.data
-    }
-}
-
 impl PartialEq<u32> for Stack {
     fn eq(&self, other: &u32) -> bool {
         self.data == *other
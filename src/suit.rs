@@ -1,8 +1,8 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use crate::stack::{CLUBS, DIAMONDS, HEARTS, SPADES};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
 pub enum Suit {
     #[default]
     Spades,
@@ -50,3 +50,18 @@ impl Display for Suit {
         write!(f, "{result}")
     }
 }
+
+impl FromStr for Suit {
+    type Err = String;
+
+    /// accepts both the symbols and the first letter of the suit
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "♠" | "S" | "s" => Ok(Suit::Spades),
+            "♣" | "C" | "c" => Ok(Suit::Clubs),
+            "♥" | "H" | "h" => Ok(Suit::Hearts),
+            "♦" | "D" | "d" => Ok(Suit::Diamonds),
+            _ => Err(format!("invalid suit '{s}'")),
+        }
+    }
+}
@@ -1,43 +1,65 @@
 use std::{
+    path::Path,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
-use crate::{game::Game, players::PlayerVec};
+use crate::{game::Game, players::PlayerVec, random, rules::Rules};
 
+/// scores are counted for the players on seats 0 and 2 of the player generator
+/// (team a) against those on seats 1 and 3 (team b), wherever they were seated
 #[derive(Default, Debug)]
 struct TournamentResult {
     scores: [i32; 2],
+    /// number of duplicate pairs in which team a won 0, 1 or 2 games
+    pairs: [usize; 3],
     num_games: usize,
     num_rounds: usize,
     duration: Duration,
 }
 
+#[derive(Default, Clone, Copy)]
+pub struct TournamentOptions<'a> {
+    pub verbose: bool,
+    /// play every deal twice, with the teams swapping seats
+    pub duplicate: bool,
+    /// write the record of every game to this directory
+    pub records_dir: Option<&'a Path>,
+}
+
 pub fn run_tournament_multithreaded(
     num_games: usize,
     num_threads: usize,
     name: &str,
-    verbose: bool,
+    rules: Rules,
+    options: TournamentOptions,
     player_gen: impl Fn() -> PlayerVec + std::marker::Send + Clone,
 ) {
+    let format = if options.duplicate { "duplicate " } else { "" };
     println!(
-        "starting tournament '{name}' with {num_threads} threads each playing {num_games} games"
+        "starting {format}tournament '{name}' with {num_threads} threads each playing {num_games} games"
     );
 
     let started = Instant::now();
     let results = Arc::new(Mutex::new(TournamentResult::default()));
 
     std::thread::scope(|s| {
-        for _ in 0..num_threads {
+        for thread_id in 0..num_threads {
             let results = Arc::clone(&results);
             let player_gen = player_gen.clone();
+            let seed = random::thread_seed();
 
             s.spawn(move || {
-                let thread_result = run_tournament(num_games, verbose, player_gen);
+                random::seed(Some(seed));
+                let thread_result =
+                    run_tournament(num_games, thread_id, rules, options, player_gen);
 
                 let mut results = results.lock().unwrap();
                 results.scores[0] += thread_result.scores[0];
                 results.scores[1] += thread_result.scores[1];
+                for (total, pairs) in results.pairs.iter_mut().zip(thread_result.pairs) {
+                    *total += pairs;
+                }
                 results.num_rounds += thread_result.num_rounds;
                 results.num_games += thread_result.num_games;
             });
@@ -50,35 +72,79 @@ pub fn run_tournament_multithreaded(
 
 fn run_tournament(
     num_games: usize,
-    verbose: bool,
+    thread_id: usize,
+    rules: Rules,
+    options: TournamentOptions,
     player_gen: impl Fn() -> PlayerVec,
 ) -> TournamentResult {
+    let start = Instant::now();
+    let mut result = TournamentResult::default();
     let mut games = Vec::with_capacity(num_games);
 
-    for _ in 0..num_games {
-        games.push(Game::new(player_gen()));
+    if options.duplicate {
+        for _ in 0..num_games.div_ceil(2) {
+            let pair = play_pair(rules, options.records_dir.is_some(), &player_gen);
+            let num_wins = pair
+                .iter()
+                .filter(|(game, team)| game.winner() == *team)
+                .count();
+            result.pairs[num_wins] += 1;
+            games.extend(pair);
+        }
+    } else {
+        for _ in 0..num_games {
+            let mut game = Game::new(rules);
+            if options.records_dir.is_some() {
+                game.enable_recording();
+            }
+            game.play(&mut player_gen());
+            games.push((game, 0));
+        }
     }
 
-    let start = Instant::now();
-    let mut total_rounds = 0;
+    for (game, team) in &games {
+        result.num_rounds += game.num_rounds();
+        result.scores[game.winner() ^ team] += 1;
 
-    for game in &mut games {
-        while !game.is_terminal() {
-            game.play_round();
+        if options.verbose {
+            println!("{}", game.winner() ^ team);
         }
-
-        total_rounds += game.num_rounds();
     }
 
-    let mut score = [0; 2];
-    for game in &mut games {
-        score[game.winner()] += 1;
+    if let Some(dir) = options.records_dir {
+        for (i, (game, _)) in games.iter().enumerate() {
+            let path = dir.join(format!("game-{thread_id}-{i}.txt"));
+            if let Err(e) = game.record().unwrap().save(&path) {
+                eprintln!("failed to write {}: {e}", path.display());
+            }
+        }
     }
 
-    TournamentResult {
-        scores: score,
-        num_rounds: total_rounds,
-        num_games,
-        duration: start.elapsed(),
-    }
+    result.num_games = games.len();
+    result.duration = start.elapsed();
+
+    result
+}
+
+/// plays the same deals twice, with the teams swapping seats. Returns both games
+/// together with the seat team of team a, which is 0 and 1 respectively
+pub fn play_pair(
+    rules: Rules,
+    recording: bool,
+    player_gen: &impl Fn() -> PlayerVec,
+) -> [(Game, usize); 2] {
+    let deal_seed = random::thread_seed();
+
+    [0, 1].map(|team| {
+        let mut players = player_gen();
+        players.rotate_right(team);
+
+        let mut game = Game::with_deal_seed(rules, deal_seed);
+        if recording {
+            game.enable_recording();
+        }
+        game.play(&mut players);
+
+        (game, team)
+    })
 }
@@ -1,12 +1,10 @@
-use std::{collections::HashMap, sync::OnceLock};
-
-use ismcts::{searcher::SearchResult, state::State};
+use ismcts::{action_list::ActionList, state::State};
 use macroquad::{
     color::{Color, DARKGRAY, WHITE, YELLOW},
     math::{vec2, Vec2},
     miniquad::window::screen_size,
     shapes::draw_circle,
-    texture::{draw_texture_ex, load_texture, DrawTextureParams, Texture2D},
+    texture::{draw_texture_ex, DrawTextureParams},
     time::get_frame_time,
     ui::{hash, root_ui, widgets, Skin},
     window::{clear_background, next_frame, screen_width},
@@ -22,9 +20,12 @@ use super::{
 use crate::{
     action::Action,
     action_collection::ActionCollection,
+    game::GameEvent,
+    players::spec::PlayerSpec,
     round::{Round, RoundPhase},
+    rules::Rules,
+    search::searcher::SearchResult,
     stack::Stack,
-    ui::card_texture,
 };
 
 pub struct App {
@@ -34,10 +35,12 @@ pub struct App {
     time_since_last_action: f32,
     wait_time: f32,
     last_search_result: Option<SearchResult<Round>>,
+    rules_index: usize,
+    trick_winner: usize,
 }
 
 impl App {
-    pub async fn new() -> Self {
+    pub async fn new(rules: Rules, players: [PlayerSpec; 4]) -> Self {
         let label_style = root_ui()
             .style_builder()
             .text_color(Color::from_rgba(180, 180, 120, 255))
@@ -50,13 +53,20 @@ impl App {
         root_ui().push_skin(&skin);
         load_textures();
 
+        let rules_index = Rules::PRESETS
+            .iter()
+            .position(|(_, preset)| *preset == rules)
+            .unwrap_or_default();
+
         App {
-            game: Default::default(),
+            game: UiGame::new(rules, players),
             moving_cards: vec![],
             returning_cards: vec![],
             time_since_last_action: 0.,
             wait_time: 0.,
             last_search_result: None,
+            rules_index,
+            trick_winner: 0,
         }
     }
 
@@ -72,10 +82,18 @@ impl App {
             self.render_stats();
             self.render_cards();
 
-            if self.game.round.phase() == RoundPhase::PickTrump && self.game.round.turn() == 0 {
-                self.render_pick_trump_message();
+            if self.game.round().turn() == 0 {
+                match self.game.round().phase() {
+                    RoundPhase::PickTrump | RoundPhase::PartnerPickTrump => {
+                        self.render_pick_trump_message()
+                    }
+                    RoundPhase::Double | RoundPhase::Redouble => self.render_double_message(),
+                    RoundPhase::PlayCards => (),
+                }
             }
-            if self.game.round.is_terminal() {
+            if self.game.is_match_over() {
+                self.render_match_over_message();
+            } else if self.game.round().is_terminal() {
                 self.render_next_round_message();
             }
 
@@ -86,18 +104,92 @@ impl App {
     fn render_next_round_message(&mut self) {
         let (width, height) = screen_size();
         if root_ui().button(vec2(width * 0.45, height * 0.65), "Next round") {
-            self.game.round.setup_for_next_round();
+            self.game.next_round();
             self.moving_cards.clear();
             self.returning_cards.clear();
         }
     }
 
+    fn render_match_over_message(&mut self) {
+        let (width, height) = screen_size();
+        let scores = self.game.scoreboard().scores();
+        let winners = match self.game.scoreboard().winner() {
+            0 => "You and your partner win",
+            _ => "The opponents win",
+        };
+
+        widgets::Group::new(hash!(), vec2(width * 0.4, height * 0.45))
+            .position(vec2(width * 0.3, height * 0.15))
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, winners);
+                ui.label(
+                    None,
+                    &format!("Final score: {} vs {}", scores[0], scores[1]),
+                );
+                for (i, summary) in self.game.scoreboard().history().iter().enumerate() {
+                    let trump = match summary.trump {
+                        Some(suit) => suit.to_string(),
+                        None => "no trump".to_owned(),
+                    };
+                    ui.label(
+                        None,
+                        &format!(
+                            "{}. dealer {}, {trump} x{}: {} vs {}, +{} for team {}",
+                            i + 1,
+                            summary.dealer,
+                            summary.multiplier,
+                            summary.card_scores[0],
+                            summary.card_scores[1],
+                            summary.points,
+                            summary.winning_team,
+                        ),
+                    );
+                }
+            });
+
+        if root_ui().button(vec2(width * 0.45, height * 0.65), "New match") {
+            self.game.new_match(Rules::PRESETS[self.rules_index].1);
+            self.moving_cards.clear();
+            self.returning_cards.clear();
+        }
+        if root_ui().button(vec2(width * 0.45, height * 0.65 + 40.), "Save record") {
+            match self.game.save_record() {
+                Ok(path) => println!("saved record to {}", path.display()),
+                Err(e) => eprintln!("failed to save record: {e}"),
+            }
+        }
+    }
+
     fn render_pick_trump_message(&mut self) {
         let (width, height) = screen_size();
         root_ui().label(vec2(width * 0.45, height * 0.5 - 70.), "Select trump");
         if root_ui().button(vec2(width * 0.45, height * 0.5), "Play without trump") {
             self.apply_action(Action::PickTrump(None));
         }
+        if self.game.round().possible_actions().has(&Action::PassTrump)
+            && root_ui().button(vec2(width * 0.45, height * 0.5 + 40.), "Pass to partner")
+        {
+            self.apply_action(Action::PassTrump);
+        }
+    }
+
+    fn render_double_message(&mut self) {
+        let (width, height) = screen_size();
+        let (label, action) = match self.game.round().phase() {
+            RoundPhase::Double => ("Double", Action::Double),
+            _ => ("Redouble", Action::Redouble),
+        };
+
+        root_ui().label(
+            vec2(width * 0.45, height * 0.5 - 70.),
+            &format!("Trump: {:?}", self.game.round().trump()),
+        );
+        if root_ui().button(vec2(width * 0.45, height * 0.5), label) {
+            self.apply_action(action);
+        }
+        if root_ui().button(vec2(width * 0.45, height * 0.5 + 40.), "Pass") {
+            self.apply_action(Action::Pass);
+        }
     }
 
     fn render_bot_icons(&self) {
@@ -120,28 +212,31 @@ impl App {
     }
 
     fn render_turn_indicator(&self) {
-        let turn = self.game.round.turn();
+        let turn = self.game.round().turn();
         let mut pos = self.get_player_position(turn);
         if turn == 0 {
             let card_size = get_card_size();
-            pos.x -=
-                card_size.x * SPACING_FACTOR * (self.game.round.player_cards(0).len() as f32) * 0.5;
+            pos.x -= card_size.x
+                * SPACING_FACTOR
+                * (self.game.round().player_cards(0).len() as f32)
+                * 0.5;
         }
         draw_circle(pos.x, pos.y, 15., YELLOW);
     }
 
     fn render_cards(&mut self) {
-        let cards = self.game.round.player_cards(0);
-        let legal_actions = self.game.round.possible_actions();
+        let cards = self.game.round().player_cards(0);
+        let legal_actions = self.game.round().possible_actions();
         let legal_cards = match legal_actions {
             ActionCollection::Cards(stack) => stack,
             ActionCollection::Trumps(_) => cards,
+            ActionCollection::Bets(_) => Stack::default(),
             _ => unreachable!(),
         };
 
         if let Some(ui_card) = Hand::draw(cards, legal_cards) {
-            match self.game.round.phase() {
-                RoundPhase::PickTrump => {
+            match self.game.round().phase() {
+                RoundPhase::PickTrump | RoundPhase::PartnerPickTrump => {
                     let trump = Some(ui_card.card.suit());
                     self.apply_action(Action::PickTrump(trump));
                 }
@@ -149,6 +244,7 @@ impl App {
                     self.apply_action(Action::PlayCard(ui_card.card));
                     self.play_card(ui_card);
                 }
+                RoundPhase::Double | RoundPhase::Redouble => unreachable!(),
             }
         }
 
@@ -165,24 +261,46 @@ impl App {
     fn render_stats(&mut self) {
         let (width, height) = screen_size();
         widgets::Group::new(hash!(), vec2(width * 0.3, height * 0.3)).ui(&mut root_ui(), |ui| {
-            ui.label(None, &format!("Trump: {:?}", self.game.round.trump()));
-            let scores = self.game.scores;
-            let round_scores = self.game.round.scores();
-            ui.label(None, &format!("Score: {} vs {}", scores[0], scores[1]));
+            ui.label(None, &format!("Trump: {:?}", self.game.round().trump()));
+            ui.label(None, &format!("Dealer: {}", self.game.round().dealer()));
+            ui.label(None, &format!("Stake: x{}", self.game.round().multiplier()));
+            let scores = self.game.scoreboard().scores();
+            let round_scores = self.game.round().scores();
+            ui.label(
+                None,
+                &format!(
+                    "Score: {} vs {} (to {})",
+                    scores[0],
+                    scores[1],
+                    self.game.round().rules().target_score
+                ),
+            );
             ui.label(
                 None,
                 &format!("Round score: {} vs {}", round_scores[0], round_scores[1]),
             );
             if let Some(s) = self.last_search_result.clone() {
-                // ui.label(None, &format!("Tree size: {}", s.tree_size));
-                ui.label(None, &format!("Ran: {} simulations", s.num_simulations));
-                ui.label(
-                    None,
-                    &format!(
-                        "at: {} sims/sec",
-                        s.num_simulations as f32 / s.duration.as_secs_f32()
-                    ),
-                );
+                if s.solved {
+                    ui.label(None, &format!("Solved: {} deals", s.num_simulations));
+                } else {
+                    ui.label(None, &format!("Tree size: {}", s.tree_size));
+                    ui.label(None, &format!("Ran: {} simulations", s.num_simulations));
+                    ui.label(
+                        None,
+                        &format!(
+                            "at: {} sims/sec",
+                            s.num_simulations as f32 / s.duration.as_secs_f32()
+                        ),
+                    );
+                }
+                // solved cards all have the same number of deals
+                let best = s.child_stats.iter().max_by(|(a, _), (b, _)| {
+                    (a.num_sims.cmp(&b.num_sims)).then(a.avg_score.total_cmp(&b.avg_score))
+                });
+                if let Some((stats, _)) = best {
+                    let score = self.game.round().card_score_of(stats.avg_score);
+                    ui.label(None, &format!("Expected: {score:.1}"));
+                }
             }
         });
         widgets::Group::new(hash!(), vec2(width * 0.3, height * 0.3))
@@ -194,11 +312,28 @@ impl App {
                     10f32..5000f32,
                     &mut self.game.think_time,
                 );
+                let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+                ui.slider(
+                    hash!(),
+                    "Threads",
+                    1f32..max_threads as f32,
+                    &mut self.game.num_threads,
+                );
+
+                // only used from the next match on, so the target score can't change
+                let names = Rules::PRESETS.map(|(name, _)| name);
+                ui.combo_box(hash!(), "Rules (next match)", &names, &mut self.rules_index);
             });
     }
 
     fn apply_action(&mut self, action: Action) {
-        self.game.apply_action(action);
+        for event in self.game.apply_action(action) {
+            #[cfg(feature = "debug")]
+            println!("{event}");
+            if let GameEvent::TrickWon { player, .. } = *event {
+                self.trick_winner = player;
+            }
+        }
         self.time_since_last_action = 0.;
     }
 
@@ -220,8 +355,7 @@ impl App {
     fn clear_old_moving_cards(&mut self) {
         if self.moving_cards.len() == 4 && self.wait_time <= 0. {
             self.returning_cards.clear();
-            let winner = self.game.round.turn();
-            let pos = self.get_player_position(winner);
+            let pos = self.get_player_position(self.trick_winner);
             for card in &mut self.moving_cards {
                 card.target_pos = Some(pos);
                 card.is_moving = true;
@@ -237,7 +371,7 @@ impl App {
     }
 
     fn get_card_target_pos(&self) -> Vec2 {
-        let i = (self.game.round.played_cards().len() + 3) % 4;
+        let i = (self.game.round().played_cards().len() + 3) % 4;
         let (width, height) = screen_size();
         let card_width = get_card_size().x;
         let padding = width * 0.5 - card_width * SPACING_FACTOR * 2.;
@@ -260,30 +394,29 @@ impl App {
     }
 
     fn check_next_ai_move(&mut self) {
-        let turn = self.game.round.turn();
+        let turn = self.game.round().turn();
         self.time_since_last_action += get_frame_time();
 
         if self.time_since_last_action > 1.
             && self.wait_time <= 0.
             && turn != 0
-            && !self.game.round.is_terminal()
+            && !self.game.round().is_terminal()
             && !self.game.is_thinking
         {
             self.game.start_thinking();
         }
 
+        if turn == 0 && !self.game.round().is_terminal() && !self.game.is_pondering() {
+            self.game.start_pondering();
+        }
+
         if self.game.is_thinking {
             if let Some(action) = self.game.load_ai_move() {
                 self.apply_action(action);
                 self.last_search_result = self.game.load_search_result();
-                match action {
-                    Action::PlayCard(card) => {
-                        let ui_card = UiCard::new(card, self.get_player_position(turn), false);
-                        self.play_card(ui_card);
-                    }
-                    Action::PickTrump(trump) => {
-                        println!("bot {turn} picked {trump:?}");
-                    }
+                if let Action::PlayCard(card) = action {
+                    let ui_card = UiCard::new(card, self.get_player_position(turn), false);
+                    self.play_card(ui_card);
                 }
             }
         }
@@ -4,13 +4,12 @@ use crate::{card::Card, stack::Stack, suit::Suit};
 use macroquad::{
     input::mouse_position,
     math::{vec2, Rect, Vec2},
-    texture::{load_texture, Texture2D},
+    texture::Texture2D,
     window::screen_width,
 };
 
 pub mod app;
 pub mod hand;
-pub mod moving_card;
 pub mod ui_card;
 pub mod ui_game;
 
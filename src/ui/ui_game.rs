@@ -1,27 +1,41 @@
 use std::{
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
-    thread,
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use ismcts::{searcher::SearchResult, state::State};
-
 use crate::{
     action::Action,
-    inference::Inference,
-    players::{mcts_player::MctsPlayer, Player},
+    game::{Game, GameEvent},
+    players::{spec::PlayerSpec, Player},
+    random,
     round::Round,
+    rules::Rules,
+    scoreboard::Scoreboard,
+    search::searcher::{share, Budget, SearchResult},
 };
 
+/// a bot that is handed between the ui thread and its search threads
+type SharedPlayer = Arc<Mutex<Box<dyn Player>>>;
+
 pub struct UiGame {
-    pub round: Round,
-    pub inference: Inference,
-    pub num_rounds: usize,
-    pub scores: [i16; 2],
+    pub game: Game,
     pub is_thinking: bool,
+    /// overrides the time budget of the bots
     pub think_time: f32,
+    /// overrides the number of search threads of the bots
+    pub num_threads: f32,
+    /// the bots, the spec of seat 0 is unused as that's the human
+    players: [PlayerSpec; 4],
+    /// the bots live as long as the game, so they can keep their search trees
+    bots: [Option<SharedPlayer>; 4],
+    /// the bots search ahead while the human thinks, until this is set
+    stop_pondering: Arc<AtomicBool>,
+    ponderers: Vec<JoinHandle<()>>,
     done_flag: Arc<AtomicBool>,
     result_slot: Arc<Mutex<Option<Action>>>,
     search_result_slot: Arc<Mutex<Option<SearchResult<Round>>>>,
@@ -29,48 +43,169 @@ pub struct UiGame {
 
 impl Default for UiGame {
     fn default() -> Self {
+        UiGame::new(Rules::default(), Default::default())
+    }
+}
+
+impl UiGame {
+    pub fn new(rules: Rules, players: [PlayerSpec; 4]) -> Self {
+        let mut game = Game::new(rules);
+        game.enable_recording();
+
+        let think_time = players[1..]
+            .iter()
+            .find_map(|spec| match spec {
+                PlayerSpec::Mcts {
+                    budget: Budget::Time(time),
+                    ..
+                } => Some(*time as f32),
+                _ => None,
+            })
+            .unwrap_or(500.);
+        let num_threads = players[1..]
+            .iter()
+            .find_map(|spec| match spec {
+                PlayerSpec::Mcts { num_threads, .. } => Some(*num_threads as f32),
+                _ => None,
+            })
+            .unwrap_or(1.);
+
         UiGame {
-            round: Round::new(0),
-            inference: Default::default(),
-            num_rounds: Default::default(),
-            scores: Default::default(),
+            game,
             is_thinking: false,
             done_flag: Arc::new(AtomicBool::new(false)),
             result_slot: Arc::new(Mutex::new(None)),
             search_result_slot: Default::default(),
-            think_time: 500.,
+            think_time,
+            num_threads,
+            players,
+            bots: std::array::from_fn(|i| {
+                (i != 0).then(|| Arc::new(Mutex::new(players[i].build())))
+            }),
+            stop_pondering: Default::default(),
+            ponderers: vec![],
         }
     }
-}
 
-impl UiGame {
-    pub fn apply_action(&mut self, action: Action) {
-        self.inference.infer(&self.round, action, self.round.turn());
-        self.round.apply_action(action);
+    pub const fn round(&self) -> &Round {
+        self.game.round()
+    }
+
+    pub const fn scoreboard(&self) -> &Scoreboard {
+        self.game.scoreboard()
+    }
+
+    pub fn is_match_over(&self) -> bool {
+        self.game.is_terminal()
+    }
+
+    pub fn next_round(&mut self) {
+        self.stop_pondering();
+        self.game.next_round();
+    }
+
+    /// the rules are fixed for a match, other rules start a new one
+    pub fn new_match(&mut self, rules: Rules) {
+        self.stop_pondering();
+        self.game = Game::new(rules);
+        self.game.enable_recording();
+    }
+
+    /// write the record of this game to the working directory
+    pub fn save_record(&self) -> std::io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = PathBuf::from(format!("manille-{timestamp}.txt"));
+        self.game.record().unwrap().save(&path)?;
 
-        if self.round.is_terminal() {
-            self.finish_round();
+        Ok(path)
+    }
+
+    pub fn apply_action(&mut self, action: Action) -> &[GameEvent] {
+        self.stop_pondering();
+        for bot in self.bots.iter().flatten() {
+            bot.lock().unwrap().observe(self.game.round(), action);
         }
+
+        self.game.apply_action(action)
     }
 
     pub fn start_thinking(&mut self) {
         self.is_thinking = true;
-        let mut ai_player = MctsPlayer::new(self.think_time as _, true);
-        let round = self.round;
-        let inference = self.inference;
+        let player = self.game.current_player();
+        let bot = Arc::clone(self.bots[player].as_ref().unwrap());
+        if let PlayerSpec::Mcts { budget, .. } = self.players[player] {
+            let mut bot = bot.lock().unwrap();
+            if let Budget::Time(_) = budget {
+                bot.set_budget(Budget::Time(self.think_time as _));
+            }
+            bot.set_num_threads(self.num_threads.round() as _);
+        }
+        let round = *self.game.round();
+        let inference = *self.game.inference();
 
         let result_slot = Arc::clone(&self.result_slot);
         let search_result_slot = Arc::clone(&self.search_result_slot);
         let done_flag = Arc::clone(&self.done_flag);
+        let seed = random::thread_seed();
 
         thread::spawn(move || {
+            random::seed(Some(seed));
+            let mut ai_player = bot.lock().unwrap();
             let action = ai_player.decide(round, &inference);
             *result_slot.lock().unwrap() = Some(action);
             *search_result_slot.lock().unwrap() = ai_player.get_last_search_result();
+            drop(ai_player);
             done_flag.store(true, Ordering::Release);
         });
     }
 
+    pub const fn is_pondering(&self) -> bool {
+        !self.ponderers.is_empty()
+    }
+
+    /// lets the bots search ahead during the human's turn, the bots pick their
+    /// tree up again once they see the human's action. The search threads are
+    /// split between the bots, the first ones to play after the human get the
+    /// extra ones, and a bot without threads doesn't ponder
+    pub fn start_pondering(&mut self) {
+        self.stop_pondering.store(false, Ordering::Relaxed);
+        let round = *self.game.round();
+        let inference = *self.game.inference();
+
+        let searchers = (1..4)
+            .filter(|&player| matches!(self.players[player], PlayerSpec::Mcts { .. }))
+            .collect::<Vec<_>>();
+        let num_threads = self.num_threads.round() as usize;
+
+        for (i, &player) in searchers.iter().enumerate() {
+            let bot_threads = share(num_threads, i, searchers.len());
+            if bot_threads == 0 {
+                continue;
+            }
+
+            let bot = Arc::clone(self.bots[player].as_ref().unwrap());
+            let stop = Arc::clone(&self.stop_pondering);
+            let seed = random::thread_seed();
+
+            self.ponderers.push(thread::spawn(move || {
+                random::seed(Some(seed));
+                let mut bot = bot.lock().unwrap();
+                bot.set_num_threads(bot_threads);
+                bot.ponder(player, round, &inference, &stop);
+            }));
+        }
+    }
+
+    fn stop_pondering(&mut self) {
+        self.stop_pondering.store(true, Ordering::Relaxed);
+        for ponderer in self.ponderers.drain(..) {
+            ponderer.join().unwrap();
+        }
+    }
+
     pub fn load_search_result(&self) -> Option<SearchResult<Round>> {
         if let Ok(s) = self.search_result_slot.lock() {
             return s.clone();
@@ -89,11 +224,4 @@ impl UiGame {
 
         None
     }
-
-    fn finish_round(&mut self) {
-        let scores = self.round.scores();
-        let winner = if scores[0] > scores[1] { 0 } else { 1 };
-        self.scores[winner] += scores[winner] - 30;
-        self.num_rounds += 1;
-    }
 }